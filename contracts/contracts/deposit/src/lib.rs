@@ -1,6 +1,6 @@
 #![no_std]
 
-use soroban_sdk::{contract, contractimpl, contracttype, log, token, Address, Env};
+use soroban_sdk::{contract, contractimpl, contracttype, log, token, Address, Env, Vec};
 
 // ─────────────────────────────────────────────
 //  Storage Keys
@@ -8,12 +8,37 @@ use soroban_sdk::{contract, contractimpl, contracttype, log, token, Address, Env
 
 #[contracttype]
 pub enum DataKey {
-    Admin,            // contract owner
-    Token,            // accepted token (XLM / USDC)
-    Balance(Address), // how much each user has deposited
-    TotalDeposits,    // sum of all deposits in the contract
+    Admin,           // contract owner
+    Token,           // accepted token (XLM / USDC)
+    Shares(Address), // vault shares held by each user
+    TotalShares,     // sum of all shares in existence
+    Locks(Address),  // fixed-term deposits held by each user
+    NextLockId,      // id handed to the next lock record
+    PenaltyBps,      // early-exit penalty in basis points
+    PrizeFund,       // penalties collected from early exits
+    TermBoost(u32),  // draw-weight boost in basis points for each lock term
+    TotalBoost,      // extra weight all open locks carry on top of their shares
 }
 
+/// A fixed-term deposit. Its shares earn vault yield like any other and
+/// cannot be withdrawn without penalty before `unlock_time`. In return the
+/// lock carries boosted draw weight: its shares times the term's boost.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LockRecord {
+    pub id: u32,
+    pub amount: i128,     // tokens originally deposited
+    pub shares: i128,     // vault shares backing this lock
+    pub unlock_time: u64, // ledger timestamp the lock matures at
+    pub term_days: u32,   // 30, 90, or 180
+    pub weight: i128,     // shares plus the term boost, fixed at lock time
+}
+
+const SECONDS_PER_DAY: u64 = 86_400;
+const DEFAULT_PENALTY_BPS: u32 = 500;
+const MAX_PENALTY_BPS: u32 = 5_000;
+const MAX_TERM_BOOST_BPS: u32 = 10_000;
+
 // ─────────────────────────────────────────────
 //  Contract
 // ─────────────────────────────────────────────
@@ -34,20 +59,26 @@ impl LuckyStake {
 
         env.storage().instance().set(&DataKey::Admin, &admin);
         env.storage().instance().set(&DataKey::Token, &token);
+        env.storage().instance().set(&DataKey::TotalShares, &0i128);
         env.storage()
             .instance()
-            .set(&DataKey::TotalDeposits, &0i128);
+            .set(&DataKey::PenaltyBps, &DEFAULT_PENALTY_BPS);
+        env.storage().instance().set(&DataKey::PrizeFund, &0i128);
     }
 
     /// User deposits `amount` tokens into the contract.
-    /// Their individual balance is tracked and added to the total.
-    pub fn deposit(env: Env, depositor: Address, amount: i128) {
+    /// Shares are minted against the vault's total assets before the deposit.
+    /// Returns the number of shares minted.
+    pub fn deposit(env: Env, depositor: Address, amount: i128) -> i128 {
         // 1. Verify the depositor signed this transaction.
         depositor.require_auth();
 
-        // 2. Amount must be positive.
+        // 2. Amount must be positive and large enough to mint a share.
         assert!(amount > 0, "deposit amount must be greater than zero");
 
+        let shares = Self::preview_deposit(env.clone(), amount);
+        assert!(shares > 0, "deposit too small to mint shares");
+
         // 3. Pull tokens from the user's wallet into this contract.
         let token_id: Address = env.storage().instance().get(&DataKey::Token).unwrap();
         let token_client = token::Client::new(&env, &token_id);
@@ -57,56 +88,468 @@ impl LuckyStake {
             &amount,
         );
 
-        // 4. Update the user's personal balance.
-        let current_balance: i128 = env
+        // 4. Mint shares to the user; the transfer already grew total assets.
+        let new_shares = Self::get_shares(env.clone(), depositor.clone()) + shares;
+        env.storage()
+            .instance()
+            .set(&DataKey::Shares(depositor.clone()), &new_shares);
+
+        let (total_assets, total_shares) = Self::totals(&env);
+        Self::set_total_shares(&env, total_shares + shares);
+
+        log!(
+            &env,
+            "Deposit: {} deposited {} for {} shares | their shares: {} | contract total: {}",
+            depositor,
+            amount,
+            shares,
+            new_shares,
+            total_assets
+        );
+
+        shares
+    }
+
+    /// User withdraws `amount` of their tokens, burning the shares needed to
+    /// cover it (rounded up in favour of the vault). Returns shares burned.
+    pub fn withdraw(env: Env, depositor: Address, amount: i128) -> i128 {
+        // 1. Verify the depositor signed this transaction.
+        depositor.require_auth();
+
+        // 2. Amount must be positive and covered by their shares.
+        assert!(amount > 0, "withdraw amount must be greater than zero");
+
+        let shares = Self::preview_withdraw(env.clone(), amount);
+        Self::burn_and_pay(&env, &depositor, shares, amount);
+
+        shares
+    }
+
+    /// User redeems `shares` for their current value in tokens.
+    /// Returns the amount of tokens paid out.
+    pub fn redeem(env: Env, depositor: Address, shares: i128) -> i128 {
+        depositor.require_auth();
+        assert!(shares > 0, "redeem shares must be greater than zero");
+
+        let amount = Self::convert_to_assets(env.clone(), shares);
+        Self::burn_and_pay(&env, &depositor, shares, amount);
+
+        amount
+    }
+
+    // ──────────────────────────────────────────
+    //  Fixed-term deposits
+    // ──────────────────────────────────────────
+
+    /// User locks `amount` tokens for `term_days` (30, 90, or 180).
+    /// Returns the id of the new lock record.
+    pub fn deposit_locked(env: Env, depositor: Address, amount: i128, term_days: u32) -> u32 {
+        depositor.require_auth();
+        assert!(amount > 0, "deposit amount must be greater than zero");
+        assert!(
+            term_days == 30 || term_days == 90 || term_days == 180,
+            "term_days must be 30, 90, or 180"
+        );
+
+        let shares = Self::preview_deposit(env.clone(), amount);
+        assert!(shares > 0, "deposit too small to mint shares");
+        let boost_bps = Self::get_term_boost(env.clone(), term_days);
+        let weight = shares * (10_000 + boost_bps as i128) / 10_000;
+
+        let token_id: Address = env.storage().instance().get(&DataKey::Token).unwrap();
+        token::Client::new(&env, &token_id).transfer(
+            &depositor,
+            &env.current_contract_address(),
+            &amount,
+        );
+
+        let (_, total_shares) = Self::totals(&env);
+        Self::set_total_shares(&env, total_shares + shares);
+        Self::add_total_boost(&env, weight - shares);
+
+        let id: u32 = env
             .storage()
             .instance()
-            .get(&DataKey::Balance(depositor.clone()))
+            .get(&DataKey::NextLockId)
             .unwrap_or(0);
-
-        let new_balance = current_balance + amount;
         env.storage()
             .instance()
-            .set(&DataKey::Balance(depositor.clone()), &new_balance);
+            .set(&DataKey::NextLockId, &(id + 1));
 
-        // 5. Update the contract-wide total.
-        let total: i128 = env
-            .storage()
-            .instance()
-            .get(&DataKey::TotalDeposits)
-            .unwrap();
+        let unlock_time = env.ledger().timestamp() + (term_days as u64) * SECONDS_PER_DAY;
+        let mut locks = Self::get_locks(env.clone(), depositor.clone());
+        locks.push_back(LockRecord {
+            id,
+            amount,
+            shares,
+            unlock_time,
+            term_days,
+            weight,
+        });
         env.storage()
             .instance()
-            .set(&DataKey::TotalDeposits, &(total + amount));
+            .set(&DataKey::Locks(depositor.clone()), &locks);
 
         log!(
             &env,
-            "Deposit: {} deposited {} | their total: {} | contract total: {}",
+            "Lock: {} locked {} for {} days | lock id: {} | unlocks at: {}",
             depositor,
             amount,
-            new_balance,
-            total + amount
+            term_days,
+            id,
+            unlock_time
+        );
+
+        id
+    }
+
+    /// User withdraws a matured lock in full. Returns the tokens paid out.
+    pub fn withdraw_unlocked(env: Env, depositor: Address, lock_id: u32) -> i128 {
+        depositor.require_auth();
+
+        let lock = Self::take_lock(&env, &depositor, lock_id);
+        assert!(
+            env.ledger().timestamp() >= lock.unlock_time,
+            "lock has not matured yet"
+        );
+
+        let amount = Self::convert_to_assets(env.clone(), lock.shares);
+        Self::pay_out(&env, &depositor, lock.shares, amount);
+
+        amount
+    }
+
+    /// User breaks a lock before it matures. The penalty is kept in the
+    /// prize fund and the rest is paid out. Returns the tokens paid out.
+    pub fn withdraw_early(env: Env, depositor: Address, lock_id: u32) -> i128 {
+        depositor.require_auth();
+
+        let lock = Self::take_lock(&env, &depositor, lock_id);
+        assert!(
+            env.ledger().timestamp() < lock.unlock_time,
+            "lock has matured, use withdraw_unlocked"
+        );
+
+        let value = Self::convert_to_assets(env.clone(), lock.shares);
+        let penalty_bps = Self::get_penalty_bps(env.clone());
+        let penalty = value * (penalty_bps as i128) / 10_000;
+        let payout = value - penalty;
+
+        // The whole value leaves the vault; the penalty stays behind as prize.
+        let (_, total_shares) = Self::totals(&env);
+        Self::set_total_shares(&env, total_shares - lock.shares);
+
+        let fund = Self::get_prize_fund(env.clone());
+        env.storage()
+            .instance()
+            .set(&DataKey::PrizeFund, &(fund + penalty));
+
+        let token_id: Address = env.storage().instance().get(&DataKey::Token).unwrap();
+        token::Client::new(&env, &token_id).transfer(
+            &env.current_contract_address(),
+            &depositor,
+            &payout,
+        );
+
+        log!(
+            &env,
+            "Early exit: {} broke lock {} | paid: {} | penalty: {}",
+            depositor,
+            lock_id,
+            payout,
+            penalty
+        );
+
+        payout
+    }
+
+    // ──────────────────────────────────────────
+    //  Admin
+    // ──────────────────────────────────────────
+
+    /// Admin moves the whole balance of some other `token` (e.g. sent to
+    /// the contract by mistake) to `to`. The vault token can never be swept:
+    /// every unit of it above the prize fund belongs to shareholders.
+    /// Returns the amount transferred.
+    ///
+    /// This replaces the earlier surplus sweep of the vault token. Since the
+    /// switch to share accounting a donation raises the share price instead
+    /// of sitting above the deposits, so there is no vault surplus left.
+    pub fn sweep_token(env: Env, token: Address, to: Address) -> i128 {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        let vault_token: Address = env.storage().instance().get(&DataKey::Token).unwrap();
+        assert!(token != vault_token, "cannot sweep the vault token");
+
+        let token_client = token::Client::new(&env, &token);
+        let held = token_client.balance(&env.current_contract_address());
+        assert!(held > 0, "nothing to sweep");
+
+        token_client.transfer(&env.current_contract_address(), &to, &held);
+
+        log!(&env, "Sweep: {} of {} sent to {}", held, token, to);
+
+        held
+    }
+
+    /// Admin sets the early-exit penalty, capped at 50%.
+    pub fn set_penalty_bps(env: Env, penalty_bps: u32) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        assert!(penalty_bps <= MAX_PENALTY_BPS, "penalty exceeds maximum");
+
+        env.storage()
+            .instance()
+            .set(&DataKey::PenaltyBps, &penalty_bps);
+
+        log!(&env, "Early-exit penalty set to {} bps", penalty_bps);
+    }
+
+    /// Admin sets the draw-weight boost for locks of `term_days`, capped at
+    /// 100% (2x weight). Applies to locks opened from now on.
+    pub fn set_term_boost(env: Env, term_days: u32, boost_bps: u32) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        assert!(
+            term_days == 30 || term_days == 90 || term_days == 180,
+            "term_days must be 30, 90, or 180"
+        );
+        assert!(boost_bps <= MAX_TERM_BOOST_BPS, "boost exceeds maximum");
+
+        env.storage()
+            .instance()
+            .set(&DataKey::TermBoost(term_days), &boost_bps);
+
+        log!(
+            &env,
+            "{}-day lock boost set to {} bps",
+            term_days,
+            boost_bps
         );
     }
 
+    /// Admin sends the collected penalties to `to` (e.g. a pool's `add_prize`
+    /// funding account). Returns the amount transferred.
+    pub fn withdraw_prize_fund(env: Env, to: Address) -> i128 {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        let fund = Self::get_prize_fund(env.clone());
+        assert!(fund > 0, "no prize to withdraw");
+
+        env.storage().instance().set(&DataKey::PrizeFund, &0i128);
+
+        let token_id: Address = env.storage().instance().get(&DataKey::Token).unwrap();
+        token::Client::new(&env, &token_id).transfer(&env.current_contract_address(), &to, &fund);
+
+        log!(&env, "Prize fund of {} sent to {}", fund, to);
+
+        fund
+    }
+
     // ──────────────────────────────────────────
     //  Read helpers
     // ──────────────────────────────────────────
 
-    /// Returns how much a specific user has deposited.
+    /// Returns the current token value of a user's unlocked shares.
     pub fn get_balance(env: Env, user: Address) -> i128 {
+        let shares = Self::get_shares(env.clone(), user);
+        Self::convert_to_assets(env, shares)
+    }
+
+    /// Returns how many vault shares a user holds.
+    pub fn get_shares(env: Env, user: Address) -> i128 {
         env.storage()
             .instance()
-            .get(&DataKey::Balance(user))
+            .get(&DataKey::Shares(user))
             .unwrap_or(0)
     }
 
-    /// Returns the total amount deposited across all users.
+    /// Returns the total assets backing all shares: every vault token the
+    /// contract holds except the prize fund. Yield and losses show up here
+    /// as soon as they hit the contract's balance.
     pub fn get_total_deposits(env: Env) -> i128 {
+        Self::totals(&env).0
+    }
+
+    /// Returns the total number of shares in existence.
+    pub fn get_total_shares(env: Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&DataKey::TotalShares)
+            .unwrap_or(0)
+    }
+
+    /// Shares that depositing `amount` tokens would mint right now.
+    pub fn preview_deposit(env: Env, amount: i128) -> i128 {
+        let (total_assets, total_shares) = Self::totals(&env);
+        // The +1 virtual share/asset keeps the first deposit 1:1. It does not
+        // stop a donation from inflating the share price; it only makes that
+        // unprofitable for small donations, as the donor loses more to the
+        // virtual share than later depositors lose to rounding.
+        amount * (total_shares + 1) / (total_assets + 1)
+    }
+
+    /// Shares that withdrawing `amount` tokens would burn right now.
+    pub fn preview_withdraw(env: Env, amount: i128) -> i128 {
+        let (total_assets, total_shares) = Self::totals(&env);
+        let numerator = amount * (total_shares + 1);
+        let denominator = total_assets + 1;
+        (numerator + denominator - 1) / denominator
+    }
+
+    /// Token value of `shares` at the current share price.
+    pub fn convert_to_assets(env: Env, shares: i128) -> i128 {
+        let (total_assets, total_shares) = Self::totals(&env);
+        shares * (total_assets + 1) / (total_shares + 1)
+    }
+
+    /// Returns a user's open fixed-term deposits.
+    pub fn get_locks(env: Env, user: Address) -> Vec<LockRecord> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Locks(user))
+            .unwrap_or_else(|| Vec::new(&env))
+    }
+
+    /// Draw-weight boost for a lock term, in basis points. Defaults to
+    /// 10% for 30 days, 25% for 90 days and 50% for 180 days.
+    pub fn get_term_boost(env: Env, term_days: u32) -> u32 {
+        let default = match term_days {
+            30 => 1_000,
+            90 => 2_500,
+            180 => 5_000,
+            _ => 0,
+        };
+        env.storage()
+            .instance()
+            .get(&DataKey::TermBoost(term_days))
+            .unwrap_or(default)
+    }
+
+    /// A user's draw weight: their liquid shares plus the boosted weight
+    /// of their open locks.
+    pub fn get_weight(env: Env, user: Address) -> i128 {
+        let mut weight = Self::get_shares(env.clone(), user.clone());
+        for lock in Self::get_locks(env, user).iter() {
+            weight += lock.weight;
+        }
+        weight
+    }
+
+    /// Draw weight across all users: every share plus the lock boosts.
+    pub fn get_total_weight(env: Env) -> i128 {
+        let boost: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::TotalBoost)
+            .unwrap_or(0);
+        Self::totals(&env).1 + boost
+    }
+
+    pub fn get_penalty_bps(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::PenaltyBps)
+            .unwrap_or(DEFAULT_PENALTY_BPS)
+    }
+
+    /// Returns the penalties collected and not yet withdrawn.
+    pub fn get_prize_fund(env: Env) -> i128 {
         env.storage()
             .instance()
-            .get(&DataKey::TotalDeposits)
+            .get(&DataKey::PrizeFund)
             .unwrap_or(0)
     }
+
+    /// Returns the contract admin.
+    pub fn get_admin(env: Env) -> Address {
+        env.storage().instance().get(&DataKey::Admin).unwrap()
+    }
 }
+
+// ─────────────────────────────────────────────
+//  Internal helpers
+// ─────────────────────────────────────────────
+
+impl LuckyStake {
+    fn totals(env: &Env) -> (i128, i128) {
+        let token_id: Address = env.storage().instance().get(&DataKey::Token).unwrap();
+        let held = token::Client::new(env, &token_id).balance(&env.current_contract_address());
+        let total_assets = held - Self::get_prize_fund(env.clone());
+        let total_shares: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::TotalShares)
+            .unwrap_or(0);
+        (total_assets, total_shares)
+    }
+
+    fn set_total_shares(env: &Env, total_shares: i128) {
+        env.storage()
+            .instance()
+            .set(&DataKey::TotalShares, &total_shares);
+    }
+
+    fn add_total_boost(env: &Env, delta: i128) {
+        let boost: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::TotalBoost)
+            .unwrap_or(0);
+        env.storage()
+            .instance()
+            .set(&DataKey::TotalBoost, &(boost + delta));
+    }
+
+    /// Burns `shares` from `depositor` and sends them `amount` tokens.
+    fn burn_and_pay(env: &Env, depositor: &Address, shares: i128, amount: i128) {
+        let current_shares = Self::get_shares(env.clone(), depositor.clone());
+        assert!(current_shares >= shares, "insufficient balance");
+
+        env.storage().instance().set(
+            &DataKey::Shares(depositor.clone()),
+            &(current_shares - shares),
+        );
+
+        Self::pay_out(env, depositor, shares, amount);
+    }
+
+    /// Removes `shares` from the vault and sends `amount` tokens to `to`.
+    fn pay_out(env: &Env, to: &Address, shares: i128, amount: i128) {
+        let (total_assets, total_shares) = Self::totals(env);
+        Self::set_total_shares(env, total_shares - shares);
+
+        let token_id: Address = env.storage().instance().get(&DataKey::Token).unwrap();
+        token::Client::new(env, &token_id).transfer(&env.current_contract_address(), to, &amount);
+
+        log!(
+            env,
+            "Withdraw: {} withdrew {} for {} shares | contract total: {}",
+            to.clone(),
+            amount,
+            shares,
+            total_assets - amount
+        );
+    }
+
+    /// Removes lock `lock_id` from `owner`'s records and returns it.
+    fn take_lock(env: &Env, owner: &Address, lock_id: u32) -> LockRecord {
+        let mut locks = Self::get_locks(env.clone(), owner.clone());
+        let index = locks
+            .iter()
+            .position(|l| l.id == lock_id)
+            .expect("lock not found");
+        let lock = locks.get(index as u32).unwrap();
+        locks.remove(index as u32);
+        Self::add_total_boost(env, lock.shares - lock.weight);
+        env.storage()
+            .instance()
+            .set(&DataKey::Locks(owner.clone()), &locks);
+        lock
+    }
+}
+
+#[cfg(test)]
 mod test;
@@ -0,0 +1,488 @@
+#![cfg(test)]
+
+extern crate std;
+
+use soroban_sdk::{
+    testutils::{Address as _, Ledger},
+    token::{StellarAssetClient, TokenClient},
+    Address, Env,
+};
+
+use crate::{LuckyStake, LuckyStakeClient};
+
+fn setup(env: &Env) -> (LuckyStakeClient<'_>, Address, Address) {
+    let admin = Address::generate(env);
+    let token = env
+        .register_stellar_asset_contract_v2(admin.clone())
+        .address();
+
+    let contract_id = env.register_contract(None, LuckyStake);
+    let client = LuckyStakeClient::new(env, &contract_id);
+
+    env.mock_all_auths();
+    client.initialize(&admin, &token);
+
+    (client, contract_id, token)
+}
+
+fn funded_user(env: &Env, token: &Address, amount: i128) -> Address {
+    let user = Address::generate(env);
+    StellarAssetClient::new(env, token).mint(&user, &amount);
+    user
+}
+
+#[test]
+fn test_single_deposit() {
+    let env = Env::default();
+    let (client, contract_id, token) = setup(&env);
+    let user = funded_user(&env, &token, 1_000);
+
+    client.deposit(&user, &400);
+
+    assert_eq!(client.get_balance(&user), 400);
+    assert_eq!(client.get_total_deposits(), 400);
+    assert_eq!(TokenClient::new(&env, &token).balance(&contract_id), 400);
+}
+
+#[test]
+fn test_multiple_deposits_same_user() {
+    let env = Env::default();
+    let (client, _contract_id, token) = setup(&env);
+    let user = funded_user(&env, &token, 1_000);
+
+    client.deposit(&user, &100);
+    client.deposit(&user, &250);
+
+    assert_eq!(client.get_balance(&user), 350);
+    assert_eq!(client.get_total_deposits(), 350);
+}
+
+#[test]
+fn test_multiple_users() {
+    let env = Env::default();
+    let (client, _contract_id, token) = setup(&env);
+    let alice = funded_user(&env, &token, 1_000);
+    let bob = funded_user(&env, &token, 1_000);
+
+    client.deposit(&alice, &300);
+    client.deposit(&bob, &700);
+
+    assert_eq!(client.get_balance(&alice), 300);
+    assert_eq!(client.get_balance(&bob), 700);
+    assert_eq!(client.get_total_deposits(), 1_000);
+}
+
+#[test]
+#[should_panic(expected = "deposit amount must be greater than zero")]
+fn test_zero_deposit_rejected() {
+    let env = Env::default();
+    let (client, _contract_id, token) = setup(&env);
+    let user = funded_user(&env, &token, 1_000);
+
+    client.deposit(&user, &0);
+}
+
+#[test]
+#[should_panic(expected = "already initialised")]
+fn test_initialize_twice_rejected() {
+    let env = Env::default();
+    let (client, _contract_id, token) = setup(&env);
+
+    client.initialize(&client.get_admin(), &token);
+}
+
+#[test]
+fn test_withdraw_returns_tokens() {
+    let env = Env::default();
+    let (client, contract_id, token) = setup(&env);
+    let user = funded_user(&env, &token, 1_000);
+    let token_client = TokenClient::new(&env, &token);
+
+    client.deposit(&user, &600);
+    client.withdraw(&user, &250);
+
+    assert_eq!(client.get_balance(&user), 350);
+    assert_eq!(client.get_total_deposits(), 350);
+    assert_eq!(token_client.balance(&user), 650);
+    assert_eq!(token_client.balance(&contract_id), 350);
+
+    client.withdraw(&user, &350);
+    assert_eq!(client.get_balance(&user), 0);
+    assert_eq!(client.get_total_deposits(), 0);
+    assert_eq!(token_client.balance(&user), 1_000);
+}
+
+#[test]
+#[should_panic(expected = "insufficient balance")]
+fn test_withdraw_more_than_balance_rejected() {
+    let env = Env::default();
+    let (client, _contract_id, token) = setup(&env);
+    let user = funded_user(&env, &token, 1_000);
+
+    client.deposit(&user, &100);
+    client.withdraw(&user, &101);
+}
+
+#[test]
+#[should_panic(expected = "insufficient balance")]
+fn test_withdraw_other_users_funds_rejected() {
+    let env = Env::default();
+    let (client, _contract_id, token) = setup(&env);
+    let alice = funded_user(&env, &token, 1_000);
+    let bob = funded_user(&env, &token, 1_000);
+
+    client.deposit(&alice, &500);
+    client.withdraw(&bob, &500);
+}
+
+#[test]
+#[should_panic(expected = "withdraw amount must be greater than zero")]
+fn test_zero_withdraw_rejected() {
+    let env = Env::default();
+    let (client, _contract_id, token) = setup(&env);
+    let user = funded_user(&env, &token, 1_000);
+
+    client.deposit(&user, &100);
+    client.withdraw(&user, &0);
+}
+
+#[test]
+#[should_panic(expected = "Error(Auth, InvalidAction)")]
+fn test_withdraw_requires_depositor_auth() {
+    let env = Env::default();
+    let (client, _contract_id, token) = setup(&env);
+    let user = funded_user(&env, &token, 1_000);
+
+    client.deposit(&user, &100);
+
+    env.set_auths(&[]);
+    client.withdraw(&user, &100);
+}
+
+#[test]
+fn test_tokens_sent_directly_are_yield() {
+    let env = Env::default();
+    let (client, contract_id, token) = setup(&env);
+    let user = funded_user(&env, &token, 1_000);
+    let token_client = TokenClient::new(&env, &token);
+
+    client.deposit(&user, &500);
+    // Tokens sent directly to the contract, outside of `deposit`.
+    StellarAssetClient::new(&env, &token).mint(&contract_id, &120);
+
+    assert_eq!(client.get_total_deposits(), 620);
+
+    // They belong to the depositor, not to the admin.
+    client.redeem(&user, &500);
+    assert_eq!(token_client.balance(&user), 1_119);
+}
+
+#[test]
+fn test_sweep_moves_other_tokens() {
+    let env = Env::default();
+    let (client, contract_id, token) = setup(&env);
+    let user = funded_user(&env, &token, 1_000);
+    let treasury = Address::generate(&env);
+
+    let stray = env
+        .register_stellar_asset_contract_v2(Address::generate(&env))
+        .address();
+    StellarAssetClient::new(&env, &stray).mint(&contract_id, &120);
+    client.deposit(&user, &500);
+
+    assert_eq!(client.sweep_token(&stray, &treasury), 120);
+    assert_eq!(TokenClient::new(&env, &stray).balance(&treasury), 120);
+    assert_eq!(client.get_total_deposits(), 500);
+}
+
+#[test]
+#[should_panic(expected = "cannot sweep the vault token")]
+fn test_sweep_vault_token_rejected() {
+    let env = Env::default();
+    let (client, contract_id, token) = setup(&env);
+    StellarAssetClient::new(&env, &token).mint(&contract_id, &120);
+
+    client.sweep_token(&token, &Address::generate(&env));
+}
+
+#[test]
+#[should_panic(expected = "Error(Auth, InvalidAction)")]
+fn test_sweep_requires_admin_auth() {
+    let env = Env::default();
+    let (client, contract_id, _token) = setup(&env);
+    let stray = env
+        .register_stellar_asset_contract_v2(Address::generate(&env))
+        .address();
+    StellarAssetClient::new(&env, &stray).mint(&contract_id, &120);
+
+    env.set_auths(&[]);
+    client.sweep_token(&stray, &Address::generate(&env));
+}
+
+#[test]
+fn test_first_deposit_mints_shares_one_to_one() {
+    let env = Env::default();
+    let (client, _contract_id, token) = setup(&env);
+    let user = funded_user(&env, &token, 1_000);
+
+    assert_eq!(client.preview_deposit(&500), 500);
+    let minted = client.deposit(&user, &500);
+
+    assert_eq!(minted, 500);
+    assert_eq!(client.get_shares(&user), 500);
+    assert_eq!(client.get_total_shares(), 500);
+}
+
+#[test]
+fn test_yield_raises_share_value() {
+    let env = Env::default();
+    let (client, contract_id, token) = setup(&env);
+    let alice = funded_user(&env, &token, 10_000);
+    let bob = funded_user(&env, &token, 10_000);
+
+    client.deposit(&alice, &1_000);
+    client.deposit(&bob, &3_000);
+
+    // 400 tokens of yield arrive and count straight away.
+    StellarAssetClient::new(&env, &token).mint(&contract_id, &400);
+    assert_eq!(client.get_total_deposits(), 4_400);
+
+    // Each share is now worth 1.1 tokens (minus rounding from the virtual share).
+    assert_eq!(client.get_balance(&alice), 1_099);
+    assert_eq!(client.get_balance(&bob), 3_299);
+    assert_eq!(client.convert_to_assets(&1_000), 1_099);
+
+    // A late depositor pays the new price: 1_100 tokens buy 1_000 shares.
+    let carol = funded_user(&env, &token, 10_000);
+    assert_eq!(client.preview_deposit(&1_100), 1_000);
+    client.deposit(&carol, &1_100);
+    assert_eq!(client.get_shares(&carol), 1_000);
+    assert_eq!(client.get_balance(&alice), 1_099);
+}
+
+#[test]
+fn test_loss_lowers_share_value() {
+    let env = Env::default();
+    let (client, contract_id, token) = setup(&env);
+    let alice = funded_user(&env, &token, 10_000);
+    let bob = funded_user(&env, &token, 10_000);
+
+    client.deposit(&alice, &1_000);
+    client.deposit(&bob, &1_000);
+
+    // Half the vault's tokens are lost.
+    TokenClient::new(&env, &token).burn(&contract_id, &1_000);
+
+    assert_eq!(client.get_total_deposits(), 1_000);
+    assert_eq!(client.get_balance(&alice), 500);
+    assert_eq!(client.get_balance(&bob), 500);
+
+    // Both can still exit with their reduced value.
+    assert_eq!(client.redeem(&alice, &1_000), 500);
+    assert_eq!(client.redeem(&bob, &1_000), 500);
+    assert_eq!(client.get_total_shares(), 0);
+}
+
+#[test]
+fn test_withdraw_burns_shares_rounded_up() {
+    let env = Env::default();
+    let (client, contract_id, token) = setup(&env);
+    let user = funded_user(&env, &token, 10_000);
+
+    client.deposit(&user, &1_000);
+    StellarAssetClient::new(&env, &token).mint(&contract_id, &1_000);
+
+    // Share price is ~2, so withdrawing 101 tokens costs 51 shares, not 50.
+    assert_eq!(client.preview_withdraw(&101), 51);
+    let burned = client.withdraw(&user, &101);
+
+    assert_eq!(burned, 51);
+    assert_eq!(client.get_shares(&user), 949);
+    assert_eq!(client.get_total_deposits(), 1_899);
+}
+
+#[test]
+#[should_panic(expected = "insufficient balance")]
+fn test_redeem_more_shares_than_held_rejected() {
+    let env = Env::default();
+    let (client, _contract_id, token) = setup(&env);
+    let user = funded_user(&env, &token, 1_000);
+
+    client.deposit(&user, &100);
+    client.redeem(&user, &101);
+}
+
+#[test]
+#[should_panic(expected = "deposit too small to mint shares")]
+fn test_dust_deposit_after_price_rise_rejected() {
+    let env = Env::default();
+    let (client, contract_id, token) = setup(&env);
+    let user = funded_user(&env, &token, 1_000);
+
+    client.deposit(&user, &1);
+    StellarAssetClient::new(&env, &token).mint(&contract_id, &1_000);
+
+    client.deposit(&user, &1);
+}
+
+const DAY: u64 = 86_400;
+
+#[test]
+fn test_locked_deposit_is_recorded() {
+    let env = Env::default();
+    let (client, _contract_id, token) = setup(&env);
+    let user = funded_user(&env, &token, 1_000);
+    env.ledger().with_mut(|l| l.timestamp = 1_000);
+
+    let id = client.deposit_locked(&user, &600, &90);
+
+    let locks = client.get_locks(&user);
+    assert_eq!(locks.len(), 1);
+    let lock = locks.get(0).unwrap();
+    assert_eq!(lock.id, id);
+    assert_eq!(lock.amount, 600);
+    assert_eq!(lock.shares, 600);
+    assert_eq!(lock.term_days, 90);
+    assert_eq!(lock.unlock_time, 1_000 + 90 * DAY);
+    assert_eq!(lock.weight, 750);
+
+    // Locked funds back the vault but are not part of the liquid balance.
+    assert_eq!(client.get_total_deposits(), 600);
+    assert_eq!(client.get_balance(&user), 0);
+}
+
+#[test]
+#[should_panic(expected = "term_days must be 30, 90, or 180")]
+fn test_locked_deposit_invalid_term_rejected() {
+    let env = Env::default();
+    let (client, _contract_id, token) = setup(&env);
+    let user = funded_user(&env, &token, 1_000);
+
+    client.deposit_locked(&user, &600, &45);
+}
+
+#[test]
+fn test_withdraw_unlocked_after_maturity() {
+    let env = Env::default();
+    let (client, _contract_id, token) = setup(&env);
+    let user = funded_user(&env, &token, 1_000);
+
+    let first = client.deposit_locked(&user, &400, &30);
+    let second = client.deposit_locked(&user, &200, &180);
+
+    env.ledger().with_mut(|l| l.timestamp += 30 * DAY);
+    assert_eq!(client.withdraw_unlocked(&user, &first), 400);
+
+    let locks = client.get_locks(&user);
+    assert_eq!(locks.len(), 1);
+    assert_eq!(locks.get(0).unwrap().id, second);
+    assert_eq!(client.get_total_deposits(), 200);
+    assert_eq!(TokenClient::new(&env, &token).balance(&user), 800);
+}
+
+#[test]
+#[should_panic(expected = "lock has not matured yet")]
+fn test_withdraw_unlocked_before_maturity_rejected() {
+    let env = Env::default();
+    let (client, _contract_id, token) = setup(&env);
+    let user = funded_user(&env, &token, 1_000);
+
+    let id = client.deposit_locked(&user, &400, &30);
+    env.ledger().with_mut(|l| l.timestamp += 29 * DAY);
+    client.withdraw_unlocked(&user, &id);
+}
+
+#[test]
+fn test_withdraw_early_pays_penalty_into_prize_fund() {
+    let env = Env::default();
+    let (client, contract_id, token) = setup(&env);
+    let user = funded_user(&env, &token, 1_000);
+    let pool = Address::generate(&env);
+    let token_client = TokenClient::new(&env, &token);
+
+    client.set_penalty_bps(&1_000);
+    let id = client.deposit_locked(&user, &500, &90);
+
+    let paid = client.withdraw_early(&user, &id);
+
+    assert_eq!(paid, 450);
+    assert_eq!(client.get_prize_fund(), 50);
+    assert_eq!(client.get_total_deposits(), 0);
+    assert_eq!(client.get_locks(&user).len(), 0);
+    assert_eq!(token_client.balance(&user), 950);
+    assert_eq!(token_client.balance(&contract_id), 50);
+
+    // The penalty is not vault yield.
+    assert_eq!(client.get_total_deposits(), 0);
+
+    assert_eq!(client.withdraw_prize_fund(&pool), 50);
+    assert_eq!(token_client.balance(&pool), 50);
+    assert_eq!(client.get_prize_fund(), 0);
+}
+
+#[test]
+#[should_panic(expected = "lock not found")]
+fn test_withdraw_other_users_lock_rejected() {
+    let env = Env::default();
+    let (client, _contract_id, token) = setup(&env);
+    let alice = funded_user(&env, &token, 1_000);
+    let bob = funded_user(&env, &token, 1_000);
+
+    let id = client.deposit_locked(&alice, &500, &30);
+    client.withdraw_early(&bob, &id);
+}
+
+#[test]
+#[should_panic(expected = "penalty exceeds maximum")]
+fn test_penalty_above_cap_rejected() {
+    let env = Env::default();
+    let (client, _contract_id, _token) = setup(&env);
+
+    client.set_penalty_bps(&5_001);
+}
+
+#[test]
+fn test_longer_lock_earns_more_weight() {
+    let env = Env::default();
+    let (client, _contract_id, token) = setup(&env);
+    let short = funded_user(&env, &token, 1_000);
+    let long = funded_user(&env, &token, 1_000);
+    let liquid = funded_user(&env, &token, 1_000);
+
+    client.deposit_locked(&short, &1_000, &30);
+    client.deposit_locked(&long, &1_000, &180);
+    client.deposit(&liquid, &1_000);
+
+    assert_eq!(client.get_weight(&short), 1_100);
+    assert_eq!(client.get_weight(&long), 1_500);
+    assert_eq!(client.get_weight(&liquid), 1_000);
+    assert_eq!(client.get_total_weight(), 3_600);
+
+    // Closing a lock drops its boost from the total.
+    let id = client.get_locks(&long).get(0).unwrap().id;
+    client.withdraw_early(&long, &id);
+    assert_eq!(client.get_weight(&long), 0);
+    assert_eq!(client.get_total_weight(), 2_100);
+}
+
+#[test]
+fn test_admin_sets_term_boost() {
+    let env = Env::default();
+    let (client, _contract_id, token) = setup(&env);
+    let user = funded_user(&env, &token, 1_000);
+
+    client.set_term_boost(&90, &4_000);
+    assert_eq!(client.get_term_boost(&90), 4_000);
+
+    client.deposit_locked(&user, &500, &90);
+    assert_eq!(client.get_weight(&user), 700);
+}
+
+#[test]
+#[should_panic(expected = "boost exceeds maximum")]
+fn test_term_boost_above_cap_rejected() {
+    let env = Env::default();
+    let (client, _contract_id, _token) = setup(&env);
+
+    client.set_term_boost(&180, &10_001);
+}
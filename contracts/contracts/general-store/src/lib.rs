@@ -1,6 +1,9 @@
 #![no_std]
 
-use soroban_sdk::{contract, contractimpl, contracttype, log, Address, Env, String};
+use soroban_sdk::{
+    contract, contracterror, contractimpl, contracttype, log, panic_with_error, symbol_short,
+    Address, Bytes, Env, IntoVal, Map, String, Symbol, Val, Vec,
+};
 
 // ─────────────────────────────────────────────
 //  Storage Key
@@ -8,7 +11,78 @@ use soroban_sdk::{contract, contractimpl, contracttype, log, Address, Env, Strin
 
 #[contracttype]
 pub enum DataKey {
-    Store(String), // any string key → any i128 value
+    Store(Address, String),   // (owner, key) → Value, in the key's chosen tier
+    KeyIndex(Address),        // owner → sorted list of their keys (persistent)
+    Version(Address, String), // (owner, key) → write counter, in the key's tier
+    History(Address, String), // (owner, key) → recent writes, oldest first, in the key's tier
+    Admin,                    // store admin, set once by initialize (instance)
+    HistoryDepth,             // how many writes are kept per key (instance)
+    Grants(Address, Address), // (owner, grantee) → write grants (persistent)
+}
+
+/// Upper bound on how many keys `list_keys` returns in one page.
+const MAX_PAGE_SIZE: u32 = 100;
+const DEFAULT_HISTORY_DEPTH: u32 = 10;
+const MAX_HISTORY_DEPTH: u32 = 50;
+/// Longest key, and so longest grant prefix, the store accepts.
+const MAX_KEY_LEN: usize = 128;
+
+// ─────────────────────────────────────────────
+//  Values & Errors
+// ─────────────────────────────────────────────
+
+/// Anything that can be stored under a key.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Value {
+    I128(i128),
+    U64(u64),
+    Bool(bool),
+    Address(Address),
+    String(String),
+    Bytes(Bytes),
+    Map(Map<String, Value>),
+}
+
+/// Where a key's value lives. Temporary entries are cheaper but are
+/// deleted for good once their TTL runs out.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum StorageTier {
+    Persistent,
+    Temporary,
+}
+
+/// One write to a key. For a removal, `removed` is set and `value` holds
+/// what was removed.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct HistoryEntry {
+    pub value: Value,
+    pub removed: bool,
+    pub writer: Address,
+    pub ledger_seq: u32,
+    pub timestamp: u64,
+}
+
+/// Lets a grantee write any of the owner's keys starting with `prefix`
+/// (an empty prefix covers every key) until `expires_at`, if set.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Grant {
+    pub prefix: String,
+    pub expires_at: Option<u64>,
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum StoreError {
+    KeyNotFound = 1,     // nothing stored under the key
+    TypeMismatch = 2,    // stored value has a different type than requested
+    ValueMismatch = 3,   // compare_and_set found a different current value
+    Overflow = 4,        // increment would overflow i128
+    VersionMismatch = 5, // set_if_version found a different current version
 }
 
 // ─────────────────────────────────────────────
@@ -20,54 +94,824 @@ pub struct GeneralStore;
 
 #[contractimpl]
 impl GeneralStore {
-    /// Store any value under any key.
-    pub fn set(env: Env, caller: Address, key: String, value: i128) {
-        caller.require_auth();
+    /// Called once after deployment. Sets the store admin and how many
+    /// writes of history are kept per key.
+    pub fn initialize(env: Env, admin: Address, history_depth: u32) {
+        if env.storage().instance().has(&DataKey::Admin) {
+            panic!("already initialised");
+        }
+        admin.require_auth();
 
-        env.storage()
+        env.storage().instance().set(&DataKey::Admin, &admin);
+        Self::store_history_depth(&env, history_depth);
+    }
+
+    /// Admin changes the history retention depth. Longer histories are
+    /// trimmed the next time their key is written.
+    pub fn set_history_depth(env: Env, history_depth: u32) {
+        let admin: Address = env
+            .storage()
             .instance()
-            .set(&DataKey::Store(key.clone()), &value);
+            .get(&DataKey::Admin)
+            .expect("not initialised");
+        admin.require_auth();
+
+        Self::store_history_depth(&env, history_depth);
+    }
+
+    /// Store an i128 under any key in `owner`'s namespace.
+    /// Returns the key's new version.
+    pub fn set(env: Env, caller: Address, owner: Address, key: String, value: i128) -> u32 {
+        Self::set_value(env, caller, owner, key, Value::I128(value))
+    }
+
+    /// Store a value of any supported type under any key in `owner`'s namespace.
+    /// Returns the key's new version.
+    pub fn set_value(env: Env, caller: Address, owner: Address, key: String, value: Value) -> u32 {
+        Self::require_writer(&env, &caller, &owner, &key);
 
-        log!(&env, "Set: key={} value={}", key, value);
+        let version = Self::write(&env, &caller, &owner, &key, &value);
+
+        log!(
+            &env,
+            "Set: owner={} key={} value={} version={}",
+            owner,
+            key,
+            value,
+            version
+        );
+
+        version
     }
 
-    /// Retrieve a value by key. Returns 0 if key doesn't exist.
-    pub fn get(env: Env, key: String) -> i128 {
+    /// Store a value in the chosen storage tier, with its TTL extended to
+    /// `ttl_ledgers`. A key that already lives in the other tier is moved,
+    /// along with its version and history.
+    /// Returns the key's new version.
+    pub fn set_with_options(
+        env: Env,
+        caller: Address,
+        owner: Address,
+        key: String,
+        value: Value,
+        tier: StorageTier,
+        ttl_ledgers: u32,
+    ) -> u32 {
+        Self::require_writer(&env, &caller, &owner, &key);
+
+        let version = Self::write_to(&env, &caller, &owner, &key, &value, tier);
+        Self::extend_key_ttl(&env, &owner, &key, tier, ttl_ledgers);
+
+        log!(
+            &env,
+            "Set: owner={} key={} value={} version={} ttl={}",
+            owner,
+            key,
+            value,
+            version,
+            ttl_ledgers
+        );
+
+        version
+    }
+
+    /// Extend the TTL of a key's value, version and history, and of the
+    /// owner's key index, to at least `ledgers`. Anyone may pay to keep an
+    /// entry alive.
+    pub fn extend_ttl(env: Env, owner: Address, key: String, ledgers: u32) {
+        let tier = Self::tier_of(&env, &owner, &key).expect("key does not exist");
+        Self::extend_key_ttl(&env, &owner, &key, tier, ledgers);
+
+        let index_key = DataKey::KeyIndex(owner);
+        if env.storage().persistent().has(&index_key) {
+            env.storage()
+                .persistent()
+                .extend_ttl(&index_key, ledgers, ledgers);
+        }
+    }
+
+    /// Extend the TTL of `grantee`'s grants in `owner`'s namespace to at
+    /// least `ledgers`, so the grantee's writes keep working. Anyone may pay.
+    pub fn extend_grant_ttl(env: Env, owner: Address, grantee: Address, ledgers: u32) {
+        let grants_key = DataKey::Grants(owner, grantee);
+        assert!(
+            env.storage().persistent().has(&grants_key),
+            "no grants to extend"
+        );
         env.storage()
-            .instance()
-            .get(&DataKey::Store(key))
-            .unwrap_or(0)
+            .persistent()
+            .extend_ttl(&grants_key, ledgers, ledgers);
+    }
+
+    /// Which storage tier a key lives in, if it exists. The SDK gives
+    /// contracts no way to read an entry's remaining TTL, so this is the
+    /// closest on-chain view; off-chain tooling can read TTLs from the RPC.
+    pub fn get_tier(env: Env, owner: Address, key: String) -> Option<StorageTier> {
+        Self::tier_of(&env, &owner, &key)
+    }
+
+    /// Write `new` only if the key currently holds `expected` (`None` means
+    /// the key must not exist). Returns the key's new version.
+    pub fn compare_and_set(
+        env: Env,
+        caller: Address,
+        owner: Address,
+        key: String,
+        expected: Option<Value>,
+        new: Value,
+    ) -> Result<u32, StoreError> {
+        Self::require_writer(&env, &caller, &owner, &key);
+
+        let current: Option<Value> = Self::get_value(env.clone(), owner.clone(), key.clone());
+        if current != expected {
+            return Err(StoreError::ValueMismatch);
+        }
+
+        let version = Self::write(&env, &caller, &owner, &key, &new);
+
+        log!(
+            &env,
+            "CompareAndSet: owner={} key={} value={} version={}",
+            owner,
+            key,
+            new,
+            version
+        );
+
+        Ok(version)
+    }
+
+    /// Write `new` only if the key is still at `expected_version` (0 means
+    /// never written). Versions only ever grow, so unlike `compare_and_set`
+    /// this also catches a value that changed and changed back.
+    /// Returns the key's new version.
+    pub fn set_if_version(
+        env: Env,
+        caller: Address,
+        owner: Address,
+        key: String,
+        expected_version: u32,
+        new: Value,
+    ) -> Result<u32, StoreError> {
+        Self::require_writer(&env, &caller, &owner, &key);
+
+        if Self::get_version(env.clone(), owner.clone(), key.clone()) != expected_version {
+            return Err(StoreError::VersionMismatch);
+        }
+
+        let version = Self::write(&env, &caller, &owner, &key, &new);
+
+        log!(
+            &env,
+            "SetIfVersion: owner={} key={} value={} version={}",
+            owner,
+            key,
+            new,
+            version
+        );
+
+        Ok(version)
+    }
+
+    /// Add `delta` to an i128 key, treating a missing key as 0.
+    /// Returns the key's new version.
+    pub fn increment(
+        env: Env,
+        caller: Address,
+        owner: Address,
+        key: String,
+        delta: i128,
+    ) -> Result<u32, StoreError> {
+        Self::require_writer(&env, &caller, &owner, &key);
+
+        let current = match Self::get_i128(env.clone(), owner.clone(), key.clone()) {
+            Ok(value) => value,
+            Err(StoreError::KeyNotFound) => 0,
+            Err(err) => return Err(err),
+        };
+        let value = current.checked_add(delta).ok_or(StoreError::Overflow)?;
+
+        let version = Self::write(&env, &caller, &owner, &key, &Value::I128(value));
+
+        log!(
+            &env,
+            "Increment: owner={} key={} value={} version={}",
+            owner,
+            key,
+            value,
+            version
+        );
+
+        Ok(version)
+    }
+
+    /// Retrieve an i128 by key. Returns 0 if key doesn't exist.
+    pub fn get(env: Env, owner: Address, key: String) -> i128 {
+        match Self::get_i128(env.clone(), owner, key) {
+            Ok(value) => value,
+            Err(StoreError::KeyNotFound) => 0,
+            Err(err) => panic_with_error!(&env, err),
+        }
+    }
+
+    /// Retrieve whatever is stored under a key, if anything.
+    pub fn get_value(env: Env, owner: Address, key: String) -> Option<Value> {
+        Self::load(&env, owner, key).ok()
+    }
+
+    pub fn get_i128(env: Env, owner: Address, key: String) -> Result<i128, StoreError> {
+        match Self::load(&env, owner, key)? {
+            Value::I128(v) => Ok(v),
+            _ => Err(StoreError::TypeMismatch),
+        }
+    }
+
+    pub fn get_u64(env: Env, owner: Address, key: String) -> Result<u64, StoreError> {
+        match Self::load(&env, owner, key)? {
+            Value::U64(v) => Ok(v),
+            _ => Err(StoreError::TypeMismatch),
+        }
+    }
+
+    pub fn get_bool(env: Env, owner: Address, key: String) -> Result<bool, StoreError> {
+        match Self::load(&env, owner, key)? {
+            Value::Bool(v) => Ok(v),
+            _ => Err(StoreError::TypeMismatch),
+        }
+    }
+
+    pub fn get_address(env: Env, owner: Address, key: String) -> Result<Address, StoreError> {
+        match Self::load(&env, owner, key)? {
+            Value::Address(v) => Ok(v),
+            _ => Err(StoreError::TypeMismatch),
+        }
+    }
+
+    pub fn get_string(env: Env, owner: Address, key: String) -> Result<String, StoreError> {
+        match Self::load(&env, owner, key)? {
+            Value::String(v) => Ok(v),
+            _ => Err(StoreError::TypeMismatch),
+        }
+    }
+
+    pub fn get_bytes(env: Env, owner: Address, key: String) -> Result<Bytes, StoreError> {
+        match Self::load(&env, owner, key)? {
+            Value::Bytes(v) => Ok(v),
+            _ => Err(StoreError::TypeMismatch),
+        }
+    }
+
+    pub fn get_map(
+        env: Env,
+        owner: Address,
+        key: String,
+    ) -> Result<Map<String, Value>, StoreError> {
+        match Self::load(&env, owner, key)? {
+            Value::Map(v) => Ok(v),
+            _ => Err(StoreError::TypeMismatch),
+        }
     }
 
     /// Check if a key exists.
-    pub fn has(env: Env, key: String) -> bool {
-        env.storage().instance().has(&DataKey::Store(key))
+    pub fn has(env: Env, owner: Address, key: String) -> bool {
+        Self::tier_of(&env, &owner, &key).is_some()
     }
 
-    /// Delete a key.
-    pub fn remove(env: Env, caller: Address, key: String) {
-        caller.require_auth();
+    /// List `owner`'s persistent keys in ascending order, starting after
+    /// `start_after` (or from the beginning), at most `limit` (capped at 100)
+    /// per page. Temporary keys are not listed: they can expire at any time.
+    pub fn list_keys(
+        env: Env,
+        owner: Address,
+        start_after: Option<String>,
+        limit: u32,
+    ) -> Vec<String> {
+        let index = Self::key_index(&env, &owner);
+        let start = match start_after {
+            Some(after) => match index.binary_search(&after) {
+                Ok(i) => i + 1,
+                Err(i) => i,
+            },
+            None => 0,
+        };
+        let end = (start + limit.min(MAX_PAGE_SIZE)).min(index.len());
+        if start >= end {
+            return Vec::new(&env);
+        }
+        index.slice(start..end)
+    }
+
+    /// Number of persistent keys stored in `owner`'s namespace.
+    pub fn count(env: Env, owner: Address) -> u32 {
+        Self::key_index(&env, &owner).len()
+    }
 
+    /// Current version of a key: the number of writes it has seen, 0 if never
+    /// written. A temporary key's version expires along with it.
+    pub fn get_version(env: Env, owner: Address, key: String) -> u32 {
+        let version_key = DataKey::Version(owner, key);
         env.storage()
-            .instance()
-            .remove(&DataKey::Store(key.clone()));
+            .persistent()
+            .get(&version_key)
+            .or_else(|| env.storage().temporary().get(&version_key))
+            .unwrap_or(0)
+    }
+
+    /// Delete a key. The version keeps counting, so a later `set` never
+    /// reuses a version a client may have already seen. Removing a key that
+    /// is not set changes nothing and publishes no event.
+    /// Returns the key's new version.
+    pub fn remove(env: Env, caller: Address, owner: Address, key: String) -> u32 {
+        Self::require_writer(&env, &caller, &owner, &key);
+
+        let version = Self::delete(&env, &caller, &owner, &key);
+
+        log!(&env, "Removed: owner={} key={}", owner, key);
+
+        version
+    }
+
+    /// The value `key` held as of ledger `ledger_seq`. Returns `None` if the
+    /// key did not exist then, or if that write has aged out of history.
+    pub fn get_at(env: Env, owner: Address, key: String, ledger_seq: u32) -> Option<Value> {
+        let history = Self::load_history(&env, &owner, &key);
+        for entry in history.iter().rev() {
+            if entry.ledger_seq <= ledger_seq {
+                return if entry.removed {
+                    None
+                } else {
+                    Some(entry.value)
+                };
+            }
+        }
+        None
+    }
+
+    /// Up to `limit` most recent writes to `key`, newest first.
+    pub fn history(env: Env, owner: Address, key: String, limit: u32) -> Vec<HistoryEntry> {
+        let mut out = Vec::new(&env);
+        for entry in Self::load_history(&env, &owner, &key).iter().rev() {
+            if out.len() >= limit {
+                break;
+            }
+            out.push_back(entry);
+        }
+        out
+    }
 
-        log!(&env, "Removed: key={}", key);
+    pub fn get_history_depth(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&DataKey::HistoryDepth)
+            .unwrap_or(DEFAULT_HISTORY_DEPTH)
     }
 
     /// Update a value — same as set, but only works if key already exists.
-    pub fn update(env: Env, caller: Address, key: String, value: i128) {
-        caller.require_auth();
+    /// Returns the key's new version.
+    pub fn update(env: Env, caller: Address, owner: Address, key: String, value: i128) -> u32 {
+        Self::require_writer(&env, &caller, &owner, &key);
 
         assert!(
-            env.storage().instance().has(&DataKey::Store(key.clone())),
+            Self::tier_of(&env, &owner, &key).is_some(),
             "key does not exist, use set() instead"
         );
 
+        let version = Self::write(&env, &caller, &owner, &key, &Value::I128(value));
+
+        log!(&env, "Updated: owner={} key={} value={}", owner, key, value);
+
+        version
+    }
+
+    // ──────────────────────────────────────────
+    //  Access control
+    // ──────────────────────────────────────────
+
+    /// Owner lets `grantee` write keys starting with `prefix` until
+    /// `expires_at` (a ledger timestamp), or indefinitely if `None`.
+    /// Re-granting the same prefix replaces its expiry.
+    pub fn grant(
+        env: Env,
+        owner: Address,
+        grantee: Address,
+        prefix: String,
+        expires_at: Option<u64>,
+    ) {
+        owner.require_auth();
+        assert!(
+            prefix.len() as usize <= MAX_KEY_LEN,
+            "prefix exceeds maximum key length"
+        );
+
+        let mut grants = Self::get_grants(env.clone(), owner.clone(), grantee.clone());
+        if let Some(i) = grants.iter().position(|g| g.prefix == prefix) {
+            grants.remove(i as u32);
+        }
+        grants.push_back(Grant {
+            prefix: prefix.clone(),
+            expires_at,
+        });
+        env.storage()
+            .persistent()
+            .set(&DataKey::Grants(owner.clone(), grantee.clone()), &grants);
+
+        env.events().publish(
+            (
+                symbol_short!("store"),
+                symbol_short!("grant"),
+                owner,
+                grantee,
+            ),
+            (prefix, expires_at),
+        );
+    }
+
+    /// Owner withdraws a grant previously given for `prefix`.
+    pub fn revoke(env: Env, owner: Address, grantee: Address, prefix: String) {
+        owner.require_auth();
+
+        let mut grants = Self::get_grants(env.clone(), owner.clone(), grantee.clone());
+        let i = grants
+            .iter()
+            .position(|g| g.prefix == prefix)
+            .expect("grant not found");
+        grants.remove(i as u32);
+
+        let grants_key = DataKey::Grants(owner.clone(), grantee.clone());
+        if grants.is_empty() {
+            env.storage().persistent().remove(&grants_key);
+        } else {
+            env.storage().persistent().set(&grants_key, &grants);
+        }
+
+        env.events().publish(
+            (
+                symbol_short!("store"),
+                symbol_short!("revoke"),
+                owner,
+                grantee,
+            ),
+            prefix,
+        );
+    }
+
+    /// Grants `owner` has given `grantee`, including expired ones.
+    pub fn get_grants(env: Env, owner: Address, grantee: Address) -> Vec<Grant> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Grants(owner, grantee))
+            .unwrap_or_else(|| Vec::new(&env))
+    }
+
+    /// Whether `caller` may currently write `key` in `owner`'s namespace.
+    pub fn can_write(env: Env, caller: Address, owner: Address, key: String) -> bool {
+        caller == owner || Self::has_grant(&env, &caller, &owner, &key)
+    }
+
+    // ──────────────────────────────────────────
+    //  Batch operations
+    // ──────────────────────────────────────────
+
+    /// Store several i128 values in `owner`'s namespace in one call. Either
+    /// every entry is written or, if any fails, none are.
+    /// Returns each key's new version, in input order.
+    pub fn set_many(
+        env: Env,
+        caller: Address,
+        owner: Address,
+        entries: Vec<(String, i128)>,
+    ) -> Vec<u32> {
+        caller.require_auth();
+
+        let mut versions = Vec::new(&env);
+        let mut keys = Vec::new(&env);
+        for (key, value) in entries.iter() {
+            Self::check_writer(&env, &caller, &owner, &key);
+            versions.push_back(Self::write(
+                &env,
+                &caller,
+                &owner,
+                &key,
+                &Value::I128(value),
+            ));
+            keys.push_back(key);
+        }
+
+        env.events().publish(
+            (symbol_short!("store"), symbol_short!("set_many"), owner),
+            keys,
+        );
+
+        versions
+    }
+
+    /// Read several i128 values at once; missing keys come back as `None`.
+    pub fn get_many(env: Env, owner: Address, keys: Vec<String>) -> Vec<Option<i128>> {
+        let mut values = Vec::new(&env);
+        for key in keys.iter() {
+            let value = match Self::get_i128(env.clone(), owner.clone(), key) {
+                Ok(value) => Some(value),
+                Err(StoreError::KeyNotFound) => None,
+                Err(err) => panic_with_error!(&env, err),
+            };
+            values.push_back(value);
+        }
+        values
+    }
+
+    /// Delete several keys from `owner`'s namespace in one call.
+    /// Returns each key's new version, in input order.
+    pub fn remove_many(env: Env, caller: Address, owner: Address, keys: Vec<String>) -> Vec<u32> {
+        caller.require_auth();
+
+        let mut versions = Vec::new(&env);
+        for key in keys.iter() {
+            Self::check_writer(&env, &caller, &owner, &key);
+            versions.push_back(Self::delete(&env, &caller, &owner, &key));
+        }
+
+        env.events().publish(
+            (
+                symbol_short!("store"),
+                Symbol::new(&env, "remove_many"),
+                owner,
+            ),
+            keys,
+        );
+
+        versions
+    }
+}
+
+// ─────────────────────────────────────────────
+//  Internal helpers
+// ─────────────────────────────────────────────
+
+impl GeneralStore {
+    fn load(env: &Env, owner: Address, key: String) -> Result<Value, StoreError> {
+        let store_key = DataKey::Store(owner, key);
+        env.storage()
+            .persistent()
+            .get(&store_key)
+            .or_else(|| env.storage().temporary().get(&store_key))
+            .ok_or(StoreError::KeyNotFound)
+    }
+
+    fn tier_of(env: &Env, owner: &Address, key: &String) -> Option<StorageTier> {
+        let store_key = DataKey::Store(owner.clone(), key.clone());
+        if env.storage().persistent().has(&store_key) {
+            Some(StorageTier::Persistent)
+        } else if env.storage().temporary().has(&store_key) {
+            Some(StorageTier::Temporary)
+        } else {
+            None
+        }
+    }
+
+    /// Writes to the key's current tier; new keys default to persistent.
+    fn write(env: &Env, writer: &Address, owner: &Address, key: &String, value: &Value) -> u32 {
+        let tier = Self::tier_of(env, owner, key).unwrap_or(StorageTier::Persistent);
+        Self::write_to(env, writer, owner, key, value, tier)
+    }
+
+    /// Stores `value` in `tier`, keeps the key index and history in sync,
+    /// publishes the write and bumps the version.
+    fn write_to(
+        env: &Env,
+        writer: &Address,
+        owner: &Address,
+        key: &String,
+        value: &Value,
+        tier: StorageTier,
+    ) -> u32 {
+        assert!(
+            key.len() as usize <= MAX_KEY_LEN,
+            "key exceeds maximum length"
+        );
+        let old = Self::load(env, owner.clone(), key.clone()).ok();
+        let store_key = DataKey::Store(owner.clone(), key.clone());
+        match tier {
+            StorageTier::Persistent => {
+                env.storage().temporary().remove(&store_key);
+                env.storage().persistent().set(&store_key, value);
+                Self::index_insert(env, owner, key);
+            }
+            StorageTier::Temporary => {
+                env.storage().persistent().remove(&store_key);
+                env.storage().temporary().set(&store_key, value);
+                // Temporary keys vanish without a write when their TTL runs
+                // out, so they are never indexed.
+                Self::index_remove(env, owner, key);
+            }
+        }
+        Self::record_history(env, writer, owner, key, value.clone(), false, tier);
+
+        let op = if old.is_some() {
+            symbol_short!("update")
+        } else {
+            symbol_short!("set")
+        };
+        Self::publish_write(env, op, owner, key, old, Some(value.clone()), writer);
+
+        Self::bump_version(env, owner, key, tier)
+    }
+
+    /// Removes the key from whichever tier holds it, recording the removal
+    /// in history, and bumps the version. A key that is not set is left
+    /// alone and keeps its version.
+    fn delete(env: &Env, writer: &Address, owner: &Address, key: &String) -> u32 {
+        let Some(tier) = Self::tier_of(env, owner, key) else {
+            return Self::get_version(env.clone(), owner.clone(), key.clone());
+        };
+        let old = Self::get_value(env.clone(), owner.clone(), key.clone());
+        if let Some(old) = old.clone() {
+            Self::record_history(env, writer, owner, key, old, true, tier);
+        }
+        let store_key = DataKey::Store(owner.clone(), key.clone());
+        env.storage().persistent().remove(&store_key);
+        env.storage().temporary().remove(&store_key);
+        Self::index_remove(env, owner, key);
+
+        Self::publish_write(env, symbol_short!("remove"), owner, key, old, None, writer);
+
+        Self::bump_version(env, owner, key, tier)
+    }
+
+    /// Emits `(store, set|update|remove, owner, key)` with data
+    /// `(old value, new value, caller)` so indexers can mirror the store.
+    /// A write to an existing key is an `update`, whichever entry point made it.
+    fn publish_write(
+        env: &Env,
+        op: Symbol,
+        owner: &Address,
+        key: &String,
+        old: Option<Value>,
+        new: Option<Value>,
+        caller: &Address,
+    ) {
+        env.events().publish(
+            (symbol_short!("store"), op, owner.clone(), key.clone()),
+            (old, new, caller.clone()),
+        );
+    }
+
+    /// Extends a key's value together with its version and history, which
+    /// live in the same tier.
+    fn extend_key_ttl(env: &Env, owner: &Address, key: &String, tier: StorageTier, ledgers: u32) {
+        for entry in [
+            DataKey::Store(owner.clone(), key.clone()),
+            DataKey::Version(owner.clone(), key.clone()),
+            DataKey::History(owner.clone(), key.clone()),
+        ] {
+            match tier {
+                StorageTier::Persistent => {
+                    if env.storage().persistent().has(&entry) {
+                        env.storage()
+                            .persistent()
+                            .extend_ttl(&entry, ledgers, ledgers);
+                    }
+                }
+                StorageTier::Temporary => {
+                    if env.storage().temporary().has(&entry) {
+                        env.storage()
+                            .temporary()
+                            .extend_ttl(&entry, ledgers, ledgers);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Stores a key's version or history in `tier`, dropping any copy an
+    /// earlier write left in the other tier, so it never outlives the value.
+    fn set_meta<V: IntoVal<Env, Val>>(env: &Env, meta: &DataKey, value: &V, tier: StorageTier) {
+        match tier {
+            StorageTier::Persistent => {
+                env.storage().temporary().remove(meta);
+                env.storage().persistent().set(meta, value);
+            }
+            StorageTier::Temporary => {
+                env.storage().persistent().remove(meta);
+                env.storage().temporary().set(meta, value);
+            }
+        }
+    }
+
+    fn load_history(env: &Env, owner: &Address, key: &String) -> Vec<HistoryEntry> {
+        let history_key = DataKey::History(owner.clone(), key.clone());
+        env.storage()
+            .persistent()
+            .get(&history_key)
+            .or_else(|| env.storage().temporary().get(&history_key))
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    fn record_history(
+        env: &Env,
+        writer: &Address,
+        owner: &Address,
+        key: &String,
+        value: Value,
+        removed: bool,
+        tier: StorageTier,
+    ) {
+        let mut history = Self::load_history(env, owner, key);
+        history.push_back(HistoryEntry {
+            value,
+            removed,
+            writer: writer.clone(),
+            ledger_seq: env.ledger().sequence(),
+            timestamp: env.ledger().timestamp(),
+        });
+        let depth = Self::get_history_depth(env.clone());
+        while history.len() > depth {
+            history.pop_front();
+        }
+        let history_key = DataKey::History(owner.clone(), key.clone());
+        Self::set_meta(env, &history_key, &history, tier);
+    }
+
+    fn store_history_depth(env: &Env, history_depth: u32) {
+        assert!(
+            history_depth > 0 && history_depth <= MAX_HISTORY_DEPTH,
+            "history_depth must be between 1 and 50"
+        );
         env.storage()
             .instance()
-            .set(&DataKey::Store(key.clone()), &value);
+            .set(&DataKey::HistoryDepth, &history_depth);
+    }
+
+    fn bump_version(env: &Env, owner: &Address, key: &String, tier: StorageTier) -> u32 {
+        let version = Self::get_version(env.clone(), owner.clone(), key.clone()) + 1;
+        let version_key = DataKey::Version(owner.clone(), key.clone());
+        Self::set_meta(env, &version_key, &version, tier);
+        version
+    }
+
+    fn key_index(env: &Env, owner: &Address) -> Vec<String> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::KeyIndex(owner.clone()))
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    fn index_insert(env: &Env, owner: &Address, key: &String) {
+        let mut index = Self::key_index(env, owner);
+        if let Err(pos) = index.binary_search(key) {
+            index.insert(pos, key.clone());
+            env.storage()
+                .persistent()
+                .set(&DataKey::KeyIndex(owner.clone()), &index);
+        }
+    }
 
-        log!(&env, "Updated: key={} value={}", key, value);
+    fn index_remove(env: &Env, owner: &Address, key: &String) {
+        let mut index = Self::key_index(env, owner);
+        if let Ok(pos) = index.binary_search(key) {
+            index.remove(pos);
+            env.storage()
+                .persistent()
+                .set(&DataKey::KeyIndex(owner.clone()), &index);
+        }
+    }
+
+    /// Authenticates `caller` and checks they may write `key`.
+    fn require_writer(env: &Env, caller: &Address, owner: &Address, key: &String) {
+        caller.require_auth();
+        Self::check_writer(env, caller, owner, key);
+    }
+
+    /// The owner may write any of their keys; anyone else needs a live
+    /// grant whose prefix matches `key`.
+    fn check_writer(env: &Env, caller: &Address, owner: &Address, key: &String) {
+        assert!(
+            caller == owner || Self::has_grant(env, caller, owner, key),
+            "caller is not the key owner"
+        );
+    }
+
+    fn has_grant(env: &Env, grantee: &Address, owner: &Address, key: &String) -> bool {
+        let now = env.ledger().timestamp();
+        Self::get_grants(env.clone(), owner.clone(), grantee.clone())
+            .iter()
+            .any(|g| g.expires_at.is_none_or(|t| now < t) && Self::starts_with(key, &g.prefix))
+    }
+
+    /// Byte-wise prefix test. Writes reject keys longer than `MAX_KEY_LEN`,
+    /// so every key a grant can apply to fits the buffers.
+    fn starts_with(key: &String, prefix: &String) -> bool {
+        let key_len = key.len() as usize;
+        let prefix_len = prefix.len() as usize;
+        if prefix_len > key_len || key_len > MAX_KEY_LEN {
+            return false;
+        }
+        let mut key_buf = [0u8; MAX_KEY_LEN];
+        let mut prefix_buf = [0u8; MAX_KEY_LEN];
+        key.copy_into_slice(&mut key_buf[..key_len]);
+        prefix.copy_into_slice(&mut prefix_buf[..prefix_len]);
+        key_buf[..prefix_len] == prefix_buf[..prefix_len]
     }
 }
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,1208 @@
+#![cfg(test)]
+
+extern crate std;
+
+use soroban_sdk::{
+    symbol_short,
+    testutils::{
+        storage::{Persistent as _, Temporary as _},
+        Address as _, AuthorizedFunction, AuthorizedInvocation, Events, Ledger,
+    },
+    vec, Address, Bytes, Env, IntoVal, Map, String, Symbol, Val, Vec,
+};
+
+use crate::{DataKey, GeneralStore, GeneralStoreClient, StorageTier, StoreError, Value};
+
+fn setup(env: &Env) -> (GeneralStoreClient<'_>, Address, Address) {
+    let contract_id = env.register_contract(None, GeneralStore);
+    let client = GeneralStoreClient::new(env, &contract_id);
+
+    env.mock_all_auths();
+
+    (client, Address::generate(env), Address::generate(env))
+}
+
+#[test]
+fn test_set_and_get() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let key = String::from_str(&env, "fee");
+
+    client.set(&alice, &alice, &key, &42);
+
+    assert_eq!(client.get(&alice, &key), 42);
+    assert!(client.has(&alice, &key));
+}
+
+#[test]
+fn test_get_missing_key_returns_zero() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let key = String::from_str(&env, "missing");
+
+    assert_eq!(client.get(&alice, &key), 0);
+    assert!(!client.has(&alice, &key));
+}
+
+#[test]
+fn test_set_overwrites() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let key = String::from_str(&env, "fee");
+
+    client.set(&alice, &alice, &key, &1);
+    client.set(&alice, &alice, &key, &-7);
+
+    assert_eq!(client.get(&alice, &key), -7);
+}
+
+#[test]
+fn test_remove() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let key = String::from_str(&env, "fee");
+
+    client.set(&alice, &alice, &key, &42);
+    client.remove(&alice, &alice, &key);
+
+    assert!(!client.has(&alice, &key));
+    assert_eq!(client.get(&alice, &key), 0);
+}
+
+#[test]
+fn test_update_existing_key() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let key = String::from_str(&env, "fee");
+
+    client.set(&alice, &alice, &key, &42);
+    client.update(&alice, &alice, &key, &43);
+
+    assert_eq!(client.get(&alice, &key), 43);
+}
+
+#[test]
+#[should_panic(expected = "key does not exist, use set() instead")]
+fn test_update_missing_key_rejected() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+
+    client.update(&alice, &alice, &String::from_str(&env, "missing"), &1);
+}
+
+#[test]
+#[should_panic(expected = "key does not exist, use set() instead")]
+fn test_update_removed_key_rejected() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let key = String::from_str(&env, "fee");
+
+    client.set(&alice, &alice, &key, &42);
+    client.remove(&alice, &alice, &key);
+    client.update(&alice, &alice, &key, &1);
+}
+
+#[test]
+fn test_set_requires_caller_auth() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let key = String::from_str(&env, "fee");
+
+    client.set(&alice, &alice, &key, &42);
+
+    assert_eq!(
+        env.auths(),
+        std::vec![(
+            alice.clone(),
+            AuthorizedInvocation {
+                function: AuthorizedFunction::Contract((
+                    client.address.clone(),
+                    Symbol::new(&env, "set"),
+                    (alice.clone(), alice.clone(), key.clone(), 42i128).into_val(&env),
+                )),
+                sub_invocations: std::vec![],
+            }
+        )]
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error(Auth, InvalidAction)")]
+fn test_set_without_auth_rejected() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+
+    env.set_auths(&[]);
+    client.set(&alice, &alice, &String::from_str(&env, "fee"), &42);
+}
+
+#[test]
+#[should_panic(expected = "Error(Auth, InvalidAction)")]
+fn test_update_without_auth_rejected() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let key = String::from_str(&env, "fee");
+
+    client.set(&alice, &alice, &key, &42);
+    env.set_auths(&[]);
+    client.update(&alice, &alice, &key, &43);
+}
+
+#[test]
+#[should_panic(expected = "Error(Auth, InvalidAction)")]
+fn test_remove_without_auth_rejected() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let key = String::from_str(&env, "fee");
+
+    client.set(&alice, &alice, &key, &42);
+    env.set_auths(&[]);
+    client.remove(&alice, &alice, &key);
+}
+
+#[test]
+#[should_panic(expected = "Error(Auth, InvalidAction)")]
+fn test_set_history_depth_requires_admin_auth() {
+    let env = Env::default();
+    let (client, _alice, _bob) = setup(&env);
+
+    client.initialize(&Address::generate(&env), &5);
+    env.set_auths(&[]);
+    client.set_history_depth(&10);
+}
+
+#[test]
+#[should_panic(expected = "already initialised")]
+fn test_initialize_twice_rejected() {
+    let env = Env::default();
+    let (client, _alice, _bob) = setup(&env);
+    let admin = Address::generate(&env);
+
+    client.initialize(&admin, &5);
+    client.initialize(&admin, &5);
+}
+
+#[test]
+fn test_owners_have_separate_namespaces() {
+    let env = Env::default();
+    let (client, alice, bob) = setup(&env);
+    let key = String::from_str(&env, "fee");
+
+    client.set(&alice, &alice, &key, &10);
+    client.set(&bob, &bob, &key, &20);
+
+    assert_eq!(client.get(&alice, &key), 10);
+    assert_eq!(client.get(&bob, &key), 20);
+}
+
+#[test]
+#[should_panic(expected = "caller is not the key owner")]
+fn test_cross_user_set_rejected() {
+    let env = Env::default();
+    let (client, alice, bob) = setup(&env);
+    let key = String::from_str(&env, "fee");
+
+    client.set(&alice, &alice, &key, &10);
+    client.set(&bob, &alice, &key, &99);
+}
+
+#[test]
+#[should_panic(expected = "caller is not the key owner")]
+fn test_cross_user_update_rejected() {
+    let env = Env::default();
+    let (client, alice, bob) = setup(&env);
+    let key = String::from_str(&env, "fee");
+
+    client.set(&alice, &alice, &key, &10);
+    client.update(&bob, &alice, &key, &99);
+}
+
+#[test]
+#[should_panic(expected = "caller is not the key owner")]
+fn test_cross_user_remove_rejected() {
+    let env = Env::default();
+    let (client, alice, bob) = setup(&env);
+    let key = String::from_str(&env, "fee");
+
+    client.set(&alice, &alice, &key, &10);
+    client.remove(&bob, &alice, &key);
+}
+
+#[test]
+fn test_owner_can_update_and_remove() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let key = String::from_str(&env, "fee");
+
+    client.set(&alice, &alice, &key, &10);
+    client.update(&alice, &alice, &key, &11);
+    assert_eq!(client.get(&alice, &key), 11);
+
+    client.remove(&alice, &alice, &key);
+    assert!(!client.has(&alice, &key));
+}
+
+#[test]
+fn test_typed_values_round_trip() {
+    let env = Env::default();
+    let (client, alice, bob) = setup(&env);
+    let treasury = String::from_str(&env, "treasury");
+    let name = String::from_str(&env, "name");
+    let blob = String::from_str(&env, "blob");
+    let flags = String::from_str(&env, "flags");
+
+    client.set_value(&alice, &alice, &treasury, &Value::Address(bob.clone()));
+    client.set_value(
+        &alice,
+        &alice,
+        &name,
+        &Value::String(String::from_str(&env, "weekly")),
+    );
+    client.set_value(
+        &alice,
+        &alice,
+        &blob,
+        &Value::Bytes(Bytes::from_array(&env, &[1, 2, 3])),
+    );
+    let mut map = Map::new(&env);
+    map.set(String::from_str(&env, "paused"), Value::Bool(false));
+    map.set(String::from_str(&env, "period"), Value::U64(7));
+    client.set_value(&alice, &alice, &flags, &Value::Map(map.clone()));
+
+    assert_eq!(client.get_address(&alice, &treasury), bob);
+    assert_eq!(
+        client.get_string(&alice, &name),
+        String::from_str(&env, "weekly")
+    );
+    assert_eq!(
+        client.get_bytes(&alice, &blob),
+        Bytes::from_array(&env, &[1, 2, 3])
+    );
+    assert_eq!(client.get_map(&alice, &flags), map);
+    assert_eq!(
+        client.get_value(&alice, &treasury),
+        Some(Value::Address(bob))
+    );
+}
+
+#[test]
+fn test_typed_getter_mismatch_returns_error() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let key = String::from_str(&env, "paused");
+
+    client.set_value(&alice, &alice, &key, &Value::Bool(true));
+
+    assert!(client.get_bool(&alice, &key));
+    assert_eq!(
+        client.try_get_address(&alice, &key),
+        Err(Ok(StoreError::TypeMismatch))
+    );
+    assert_eq!(
+        client.try_get_i128(&alice, &key),
+        Err(Ok(StoreError::TypeMismatch))
+    );
+}
+
+#[test]
+fn test_typed_getter_missing_key_returns_error() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let key = String::from_str(&env, "missing");
+
+    assert_eq!(client.get_value(&alice, &key), None);
+    assert_eq!(
+        client.try_get_u64(&alice, &key),
+        Err(Ok(StoreError::KeyNotFound))
+    );
+    // The i128 getter keeps its "missing means zero" behaviour.
+    assert_eq!(client.get(&alice, &key), 0);
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #2)")]
+fn test_get_on_non_integer_panics() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let key = String::from_str(&env, "paused");
+
+    client.set_value(&alice, &alice, &key, &Value::Bool(true));
+    client.get(&alice, &key);
+}
+
+#[test]
+fn test_list_keys_sorted_and_paginated() {
+    let env = Env::default();
+    let (client, alice, bob) = setup(&env);
+
+    for name in ["period", "fee", "cap", "tiers", "min"] {
+        client.set(&alice, &alice, &String::from_str(&env, name), &1);
+    }
+    client.set(&bob, &bob, &String::from_str(&env, "other"), &1);
+
+    assert_eq!(client.count(&alice), 5);
+    assert_eq!(client.count(&bob), 1);
+
+    let first = client.list_keys(&alice, &None, &2);
+    assert_eq!(
+        first,
+        vec![
+            &env,
+            String::from_str(&env, "cap"),
+            String::from_str(&env, "fee")
+        ]
+    );
+
+    let next = client.list_keys(&alice, &Some(first.last().unwrap()), &2);
+    assert_eq!(
+        next,
+        vec![
+            &env,
+            String::from_str(&env, "min"),
+            String::from_str(&env, "period")
+        ]
+    );
+
+    let last = client.list_keys(&alice, &Some(next.last().unwrap()), &2);
+    assert_eq!(last, vec![&env, String::from_str(&env, "tiers")]);
+
+    let done = client.list_keys(&alice, &Some(last.last().unwrap()), &2);
+    assert_eq!(done.len(), 0);
+}
+
+#[test]
+fn test_key_index_tracks_set_update_and_remove() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let fee = String::from_str(&env, "fee");
+    let cap = String::from_str(&env, "cap");
+
+    client.set(&alice, &alice, &fee, &1);
+    client.set(&alice, &alice, &fee, &2);
+    client.update(&alice, &alice, &fee, &3);
+    client.set_value(&alice, &alice, &cap, &Value::U64(9));
+    assert_eq!(client.count(&alice), 2);
+
+    client.remove(&alice, &alice, &fee);
+    assert_eq!(client.count(&alice), 1);
+    assert_eq!(
+        client.list_keys(&alice, &None, &10),
+        vec![&env, cap.clone()]
+    );
+
+    // Removing a key that is already gone leaves the index alone.
+    client.remove(&alice, &alice, &fee);
+    assert_eq!(client.count(&alice), 1);
+}
+
+#[test]
+fn test_every_write_bumps_version() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let key = String::from_str(&env, "fee");
+
+    assert_eq!(client.get_version(&alice, &key), 0);
+    assert_eq!(client.set(&alice, &alice, &key, &1), 1);
+    assert_eq!(client.update(&alice, &alice, &key, &2), 2);
+    assert_eq!(client.remove(&alice, &alice, &key), 3);
+    // A re-created key continues from where it left off.
+    assert_eq!(client.set_value(&alice, &alice, &key, &Value::U64(5)), 4);
+    assert_eq!(client.get_version(&alice, &key), 4);
+}
+
+#[test]
+fn test_compare_and_set() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let key = String::from_str(&env, "fee");
+
+    // `None` expects the key to be absent.
+    assert_eq!(
+        client.compare_and_set(&alice, &alice, &key, &None, &Value::I128(10)),
+        1
+    );
+    assert_eq!(
+        client.try_compare_and_set(&alice, &alice, &key, &None, &Value::I128(20)),
+        Err(Ok(StoreError::ValueMismatch))
+    );
+    assert_eq!(
+        client.try_compare_and_set(
+            &alice,
+            &alice,
+            &key,
+            &Some(Value::I128(11)),
+            &Value::I128(20)
+        ),
+        Err(Ok(StoreError::ValueMismatch))
+    );
+    assert_eq!(client.get(&alice, &key), 10);
+
+    assert_eq!(
+        client.compare_and_set(
+            &alice,
+            &alice,
+            &key,
+            &Some(Value::I128(10)),
+            &Value::I128(20)
+        ),
+        2
+    );
+    assert_eq!(client.get(&alice, &key), 20);
+}
+
+#[test]
+fn test_set_if_version() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let key = String::from_str(&env, "fee");
+
+    // Version 0 expects the key to never have been written.
+    assert_eq!(
+        client.set_if_version(&alice, &alice, &key, &0, &Value::I128(10)),
+        1
+    );
+    assert_eq!(
+        client.try_set_if_version(&alice, &alice, &key, &0, &Value::I128(20)),
+        Err(Ok(StoreError::VersionMismatch))
+    );
+
+    // The value goes 10 -> 20 -> 10: a value check can't tell, a version check can.
+    client.set(&alice, &alice, &key, &20);
+    client.set(&alice, &alice, &key, &10);
+    assert_eq!(
+        client.try_set_if_version(&alice, &alice, &key, &1, &Value::I128(30)),
+        Err(Ok(StoreError::VersionMismatch))
+    );
+    assert_eq!(client.get(&alice, &key), 10);
+
+    assert_eq!(
+        client.set_if_version(&alice, &alice, &key, &3, &Value::I128(30)),
+        4
+    );
+    assert_eq!(client.get(&alice, &key), 30);
+}
+
+#[test]
+fn test_increment() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let key = String::from_str(&env, "draws");
+
+    assert_eq!(client.increment(&alice, &alice, &key, &5), 1);
+    assert_eq!(client.increment(&alice, &alice, &key, &-2), 2);
+    assert_eq!(client.get(&alice, &key), 3);
+}
+
+#[test]
+fn test_increment_overflow_and_type_errors() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let big = String::from_str(&env, "big");
+    let flag = String::from_str(&env, "flag");
+
+    client.set(&alice, &alice, &big, &i128::MAX);
+    assert_eq!(
+        client.try_increment(&alice, &alice, &big, &1),
+        Err(Ok(StoreError::Overflow))
+    );
+    assert_eq!(client.get(&alice, &big), i128::MAX);
+    assert_eq!(client.get_version(&alice, &big), 1);
+
+    client.set_value(&alice, &alice, &flag, &Value::Bool(true));
+    assert_eq!(
+        client.try_increment(&alice, &alice, &flag, &1),
+        Err(Ok(StoreError::TypeMismatch))
+    );
+}
+
+#[test]
+#[should_panic(expected = "caller is not the key owner")]
+fn test_cross_user_increment_rejected() {
+    let env = Env::default();
+    let (client, alice, bob) = setup(&env);
+
+    client.increment(&bob, &alice, &String::from_str(&env, "draws"), &1);
+}
+
+fn set_ledger(env: &Env, sequence: u32, timestamp: u64) {
+    env.ledger().with_mut(|l| {
+        l.sequence_number = sequence;
+        l.timestamp = timestamp;
+    });
+}
+
+#[test]
+fn test_history_records_writes_newest_first() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let key = String::from_str(&env, "fee");
+
+    set_ledger(&env, 100, 1_000);
+    client.set(&alice, &alice, &key, &1);
+    set_ledger(&env, 110, 1_050);
+    client.update(&alice, &alice, &key, &2);
+    set_ledger(&env, 120, 1_100);
+    client.remove(&alice, &alice, &key);
+
+    let history = client.history(&alice, &key, &10);
+    assert_eq!(history.len(), 3);
+
+    let latest = history.get(0).unwrap();
+    assert!(latest.removed);
+    assert_eq!(latest.value, Value::I128(2));
+    assert_eq!(latest.ledger_seq, 120);
+
+    let first = history.get(2).unwrap();
+    assert!(!first.removed);
+    assert_eq!(first.value, Value::I128(1));
+    assert_eq!(first.writer, alice);
+    assert_eq!(first.ledger_seq, 100);
+    assert_eq!(first.timestamp, 1_000);
+
+    assert_eq!(client.history(&alice, &key, &1).len(), 1);
+}
+
+#[test]
+fn test_get_at_returns_point_in_time_value() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let key = String::from_str(&env, "fee");
+
+    set_ledger(&env, 100, 1_000);
+    client.set(&alice, &alice, &key, &1);
+    set_ledger(&env, 110, 1_050);
+    client.set(&alice, &alice, &key, &2);
+    set_ledger(&env, 120, 1_100);
+    client.remove(&alice, &alice, &key);
+
+    assert_eq!(client.get_at(&alice, &key, &99), None);
+    assert_eq!(client.get_at(&alice, &key, &100), Some(Value::I128(1)));
+    assert_eq!(client.get_at(&alice, &key, &115), Some(Value::I128(2)));
+    assert_eq!(client.get_at(&alice, &key, &120), None);
+}
+
+#[test]
+fn test_history_depth_is_bounded_and_configurable() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let admin = Address::generate(&env);
+    let key = String::from_str(&env, "fee");
+
+    client.initialize(&admin, &3);
+    assert_eq!(client.get_history_depth(), 3);
+
+    for i in 0..5 {
+        set_ledger(&env, 100 + i, 1_000);
+        client.set(&alice, &alice, &key, &(i as i128));
+    }
+
+    let history = client.history(&alice, &key, &10);
+    assert_eq!(history.len(), 3);
+    assert_eq!(history.get(2).unwrap().value, Value::I128(2));
+    // The oldest writes have aged out.
+    assert_eq!(client.get_at(&alice, &key, &101), None);
+
+    client.set_history_depth(&1);
+    client.set(&alice, &alice, &key, &9);
+    assert_eq!(client.history(&alice, &key, &10).len(), 1);
+}
+
+#[test]
+#[should_panic(expected = "history_depth must be between 1 and 50")]
+fn test_history_depth_above_max_rejected() {
+    let env = Env::default();
+    let (client, _alice, _bob) = setup(&env);
+
+    client.initialize(&Address::generate(&env), &51);
+}
+
+#[test]
+fn test_storage_tier_selection() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let session = String::from_str(&env, "session");
+    let fee = String::from_str(&env, "fee");
+
+    client.set(&alice, &alice, &fee, &5);
+    client.set_with_options(
+        &alice,
+        &alice,
+        &session,
+        &Value::U64(1),
+        &StorageTier::Temporary,
+        &100,
+    );
+
+    assert_eq!(client.get_tier(&alice, &fee), Some(StorageTier::Persistent));
+    assert_eq!(
+        client.get_tier(&alice, &session),
+        Some(StorageTier::Temporary)
+    );
+    assert_eq!(client.get_u64(&alice, &session), 1);
+
+    // Plain writes keep a key in its current tier.
+    client.set_value(&alice, &alice, &session, &Value::U64(2));
+    assert_eq!(
+        client.get_tier(&alice, &session),
+        Some(StorageTier::Temporary)
+    );
+
+    // Choosing the other tier moves the key.
+    client.set_with_options(
+        &alice,
+        &alice,
+        &session,
+        &Value::U64(3),
+        &StorageTier::Persistent,
+        &5_000,
+    );
+    assert_eq!(
+        client.get_tier(&alice, &session),
+        Some(StorageTier::Persistent)
+    );
+    assert_eq!(client.get_u64(&alice, &session), 3);
+
+    client.remove(&alice, &alice, &session);
+    assert_eq!(client.get_tier(&alice, &session), None);
+    assert_eq!(client.count(&alice), 1);
+}
+
+#[test]
+fn test_temporary_keys_stay_out_of_the_index() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let session = String::from_str(&env, "session");
+    let fee = String::from_str(&env, "fee");
+
+    client.set(&alice, &alice, &fee, &5);
+    client.set_with_options(
+        &alice,
+        &alice,
+        &session,
+        &Value::U64(1),
+        &StorageTier::Temporary,
+        &10,
+    );
+    assert_eq!(client.count(&alice), 1);
+
+    // Once the temporary key expires, the index still matches what exists.
+    env.ledger().with_mut(|l| l.sequence_number += 20);
+    assert!(!client.has(&alice, &session));
+    assert_eq!(client.count(&alice), 1);
+    assert_eq!(
+        client.list_keys(&alice, &None, &10),
+        Vec::from_array(&env, [fee.clone()])
+    );
+
+    // Moving a persistent key to the temporary tier unindexes it.
+    client.set_with_options(
+        &alice,
+        &alice,
+        &fee,
+        &Value::I128(6),
+        &StorageTier::Temporary,
+        &100,
+    );
+    assert_eq!(client.count(&alice), 0);
+}
+
+#[test]
+fn test_ttl_set_and_extended() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let session = String::from_str(&env, "session");
+    let fee = String::from_str(&env, "fee");
+    let session_key = DataKey::Store(alice.clone(), session.clone());
+    let fee_key = DataKey::Store(alice.clone(), fee.clone());
+
+    client.set_with_options(
+        &alice,
+        &alice,
+        &session,
+        &Value::Bool(true),
+        &StorageTier::Temporary,
+        &500,
+    );
+    client.set(&alice, &alice, &fee, &5);
+
+    env.as_contract(&client.address, || {
+        assert_eq!(env.storage().temporary().get_ttl(&session_key), 500);
+    });
+
+    client.extend_ttl(&alice, &session, &2_000);
+    client.extend_ttl(&alice, &fee, &10_000);
+
+    env.as_contract(&client.address, || {
+        assert_eq!(env.storage().temporary().get_ttl(&session_key), 2_000);
+        assert_eq!(env.storage().persistent().get_ttl(&fee_key), 10_000);
+        assert_eq!(
+            env.storage()
+                .persistent()
+                .get_ttl(&DataKey::Version(alice.clone(), fee.clone())),
+            10_000
+        );
+        assert_eq!(
+            env.storage()
+                .persistent()
+                .get_ttl(&DataKey::KeyIndex(alice.clone())),
+            10_000
+        );
+    });
+}
+
+#[test]
+fn test_temporary_key_metadata_lives_in_its_tier() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let session = String::from_str(&env, "session");
+    let version_key = DataKey::Version(alice.clone(), session.clone());
+    let history_key = DataKey::History(alice.clone(), session.clone());
+
+    client.set_with_options(
+        &alice,
+        &alice,
+        &session,
+        &Value::U64(1),
+        &StorageTier::Temporary,
+        &500,
+    );
+
+    env.as_contract(&client.address, || {
+        assert!(!env.storage().persistent().has(&version_key));
+        assert!(!env.storage().persistent().has(&history_key));
+        assert_eq!(env.storage().temporary().get_ttl(&version_key), 500);
+        assert_eq!(env.storage().temporary().get_ttl(&history_key), 500);
+    });
+
+    // Moving the key takes its version and history along.
+    client.set_with_options(
+        &alice,
+        &alice,
+        &session,
+        &Value::U64(2),
+        &StorageTier::Persistent,
+        &5_000,
+    );
+    assert_eq!(client.get_version(&alice, &session), 2);
+    assert_eq!(client.history(&alice, &session, &10).len(), 2);
+    env.as_contract(&client.address, || {
+        assert!(!env.storage().temporary().has(&version_key));
+        assert!(!env.storage().temporary().has(&history_key));
+        assert!(env.storage().persistent().has(&version_key));
+    });
+
+    // Back in the temporary tier, everything expires together.
+    client.set_with_options(
+        &alice,
+        &alice,
+        &session,
+        &Value::U64(3),
+        &StorageTier::Temporary,
+        &10,
+    );
+    env.ledger().with_mut(|l| l.sequence_number += 20);
+    assert!(!client.has(&alice, &session));
+    assert_eq!(client.get_version(&alice, &session), 0);
+    assert_eq!(client.history(&alice, &session, &10).len(), 0);
+}
+
+#[test]
+fn test_extend_grant_ttl() {
+    let env = Env::default();
+    let (client, alice, bob) = setup(&env);
+
+    client.grant(&alice, &bob, &String::from_str(&env, "pool."), &None);
+    client.extend_grant_ttl(&alice, &bob, &10_000);
+
+    env.as_contract(&client.address, || {
+        assert_eq!(
+            env.storage()
+                .persistent()
+                .get_ttl(&DataKey::Grants(alice.clone(), bob.clone())),
+            10_000
+        );
+    });
+}
+
+#[test]
+#[should_panic(expected = "key does not exist")]
+fn test_extend_ttl_missing_key_rejected() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+
+    client.extend_ttl(&alice, &String::from_str(&env, "missing"), &1_000);
+}
+
+#[test]
+fn test_set_many_and_get_many() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let fee = String::from_str(&env, "fee");
+    let cap = String::from_str(&env, "cap");
+    let missing = String::from_str(&env, "missing");
+
+    client.set(&alice, &alice, &fee, &1);
+    let versions = client.set_many(
+        &alice,
+        &alice,
+        &vec![&env, (fee.clone(), 25i128), (cap.clone(), 1_000i128)],
+    );
+
+    assert_eq!(versions, vec![&env, 2u32, 1u32]);
+    assert_eq!(
+        client.get_many(&alice, &vec![&env, fee.clone(), missing, cap.clone()]),
+        vec![&env, Some(25i128), None, Some(1_000i128)]
+    );
+    assert_eq!(client.count(&alice), 2);
+
+    let event = env.events().all().last().unwrap();
+    assert_eq!(
+        event.1,
+        (
+            symbol_short!("store"),
+            symbol_short!("set_many"),
+            alice.clone()
+        )
+            .into_val(&env)
+    );
+    let keys: Vec<String> = event.2.into_val(&env);
+    assert_eq!(keys, vec![&env, fee, cap]);
+}
+
+#[test]
+fn test_remove_many() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let fee = String::from_str(&env, "fee");
+    let cap = String::from_str(&env, "cap");
+    let min = String::from_str(&env, "min");
+
+    client.set_many(
+        &alice,
+        &alice,
+        &vec![
+            &env,
+            (fee.clone(), 1i128),
+            (cap.clone(), 2i128),
+            (min.clone(), 3i128),
+        ],
+    );
+    client.remove_many(&alice, &alice, &vec![&env, fee.clone(), min.clone()]);
+
+    assert_eq!(client.list_keys(&alice, &None, &10), vec![&env, cap]);
+
+    let event = env.events().all().last().unwrap();
+    assert_eq!(
+        event.1,
+        (
+            symbol_short!("store"),
+            Symbol::new(&env, "remove_many"),
+            alice.clone()
+        )
+            .into_val(&env)
+    );
+    let keys: Vec<String> = event.2.into_val(&env);
+    assert_eq!(keys, vec![&env, fee, min]);
+}
+
+#[test]
+fn test_set_many_is_atomic() {
+    let env = Env::default();
+    let (client, alice, backend) = setup(&env);
+    let fee = String::from_str(&env, "pool.fee");
+    let admin = String::from_str(&env, "admin");
+
+    client.set(&alice, &alice, &fee, &5);
+    client.grant(&alice, &backend, &String::from_str(&env, "pool."), &None);
+
+    // The first entry is allowed, the second is not: nothing is written.
+    let result = client.try_set_many(
+        &backend,
+        &alice,
+        &vec![&env, (fee.clone(), 1i128), (admin.clone(), 1i128)],
+    );
+    assert!(result.is_err());
+
+    assert_eq!(client.get(&alice, &fee), 5);
+    assert_eq!(client.get_version(&alice, &fee), 1);
+    assert_eq!(client.history(&alice, &fee, &10).len(), 1);
+    assert!(!client.has(&alice, &admin));
+}
+
+#[test]
+#[should_panic(expected = "Error(Contract, #2)")]
+fn test_get_many_type_mismatch_panics() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let flag = String::from_str(&env, "flag");
+
+    client.set_value(&alice, &alice, &flag, &Value::Bool(true));
+    client.get_many(&alice, &vec![&env, flag]);
+}
+
+type WriteEvent = (Option<Value>, Option<Value>, Address);
+
+fn last_write_event(env: &Env) -> (Vec<Val>, WriteEvent) {
+    let (_contract, topics, data) = env.events().all().last().unwrap();
+    (topics, data.into_val(env))
+}
+
+#[test]
+fn test_set_update_remove_publish_events() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let key = String::from_str(&env, "fee");
+
+    client.set(&alice, &alice, &key, &1);
+    let (topics, data) = last_write_event(&env);
+    assert_eq!(
+        topics,
+        (
+            symbol_short!("store"),
+            symbol_short!("set"),
+            alice.clone(),
+            key.clone()
+        )
+            .into_val(&env)
+    );
+    assert_eq!(data, (None, Some(Value::I128(1)), alice.clone()));
+
+    client.update(&alice, &alice, &key, &2);
+    let (topics, data) = last_write_event(&env);
+    assert_eq!(
+        topics,
+        (
+            symbol_short!("store"),
+            symbol_short!("update"),
+            alice.clone(),
+            key.clone()
+        )
+            .into_val(&env)
+    );
+    assert_eq!(
+        data,
+        (Some(Value::I128(1)), Some(Value::I128(2)), alice.clone())
+    );
+
+    client.remove(&alice, &alice, &key);
+    let (topics, data) = last_write_event(&env);
+    assert_eq!(
+        topics,
+        (
+            symbol_short!("store"),
+            symbol_short!("remove"),
+            alice.clone(),
+            key.clone()
+        )
+            .into_val(&env)
+    );
+    assert_eq!(data, (Some(Value::I128(2)), None, alice.clone()));
+}
+
+#[test]
+fn test_remove_missing_key_is_a_no_op() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let key = String::from_str(&env, "fee");
+
+    assert_eq!(client.remove(&alice, &alice, &key), 0);
+    assert!(env.events().all().is_empty());
+    assert_eq!(client.get_version(&alice, &key), 0);
+    assert!(client.history(&alice, &key, &10).is_empty());
+
+    client.set(&alice, &alice, &key, &1);
+    assert_eq!(client.remove(&alice, &alice, &key), 2);
+    let published = env.events().all().len();
+    assert_eq!(client.remove(&alice, &alice, &key), 2);
+    assert_eq!(env.events().all().len(), published);
+    assert_eq!(client.history(&alice, &key, &10).len(), 2);
+}
+
+#[test]
+fn test_overwrite_via_set_publishes_update() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let key = String::from_str(&env, "fee");
+
+    client.set(&alice, &alice, &key, &1);
+    client.increment(&alice, &alice, &key, &4);
+
+    let (topics, data) = last_write_event(&env);
+    assert_eq!(
+        topics,
+        (
+            symbol_short!("store"),
+            symbol_short!("update"),
+            alice.clone(),
+            key.clone()
+        )
+            .into_val(&env)
+    );
+    assert_eq!(data, (Some(Value::I128(1)), Some(Value::I128(5)), alice));
+}
+
+#[test]
+fn test_set_many_publishes_one_event_per_key() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let fee = String::from_str(&env, "fee");
+    let cap = String::from_str(&env, "cap");
+
+    client.set_many(
+        &alice,
+        &alice,
+        &vec![&env, (fee.clone(), 1i128), (cap.clone(), 2i128)],
+    );
+
+    // Two per-key events followed by the batch summary.
+    let events = env.events().all();
+    assert_eq!(events.len(), 3);
+    let (_contract, topics, _data) = events.get(1).unwrap();
+    assert_eq!(
+        topics,
+        (symbol_short!("store"), symbol_short!("set"), alice, cap).into_val(&env)
+    );
+}
+
+#[test]
+fn test_grantee_can_write_matching_prefix() {
+    let env = Env::default();
+    let (client, alice, backend) = setup(&env);
+    let pool_fee = String::from_str(&env, "pool.fee");
+    let pool_cap = String::from_str(&env, "pool.cap");
+
+    client.grant(&alice, &backend, &String::from_str(&env, "pool."), &None);
+
+    client.set(&backend, &alice, &pool_fee, &25);
+    client.update(&backend, &alice, &pool_fee, &30);
+    client.increment(&backend, &alice, &pool_fee, &1);
+    client.set_many(&backend, &alice, &vec![&env, (pool_cap.clone(), 9i128)]);
+    client.remove(&backend, &alice, &pool_cap);
+
+    assert_eq!(client.get(&alice, &pool_fee), 31);
+    assert!(!client.has(&alice, &pool_cap));
+    assert!(client.can_write(&backend, &alice, &pool_fee));
+    assert!(!client.can_write(&backend, &alice, &String::from_str(&env, "admin")));
+}
+
+#[test]
+#[should_panic(expected = "caller is not the key owner")]
+fn test_grantee_cannot_write_outside_prefix() {
+    let env = Env::default();
+    let (client, alice, backend) = setup(&env);
+
+    client.grant(&alice, &backend, &String::from_str(&env, "pool."), &None);
+    client.set(&backend, &alice, &String::from_str(&env, "admin"), &1);
+}
+
+#[test]
+fn test_grantee_can_write_longest_key() {
+    let env = Env::default();
+    let (client, alice, backend) = setup(&env);
+    let key = String::from_str(&env, &std::format!("pool.{}", "x".repeat(123)));
+    assert_eq!(key.len(), 128);
+
+    client.grant(&alice, &backend, &String::from_str(&env, "pool."), &None);
+    client.set(&backend, &alice, &key, &1);
+    assert_eq!(client.get(&alice, &key), 1);
+}
+
+#[test]
+#[should_panic(expected = "key exceeds maximum length")]
+fn test_key_longer_than_max_rejected() {
+    let env = Env::default();
+    let (client, alice, _bob) = setup(&env);
+    let key = String::from_str(&env, &std::format!("pool.{}", "x".repeat(124)));
+
+    client.set(&alice, &alice, &key, &1);
+}
+
+#[test]
+#[should_panic(expected = "caller is not the key owner")]
+fn test_grant_expires() {
+    let env = Env::default();
+    let (client, alice, backend) = setup(&env);
+    let key = String::from_str(&env, "pool.fee");
+
+    set_ledger(&env, 1, 1_000);
+    client.grant(
+        &alice,
+        &backend,
+        &String::from_str(&env, "pool."),
+        &Some(2_000),
+    );
+    client.set(&backend, &alice, &key, &1);
+
+    set_ledger(&env, 2, 2_000);
+    client.set(&backend, &alice, &key, &2);
+}
+
+#[test]
+#[should_panic(expected = "caller is not the key owner")]
+fn test_revoked_grant_rejected() {
+    let env = Env::default();
+    let (client, alice, backend) = setup(&env);
+    let prefix = String::from_str(&env, "pool.");
+    let key = String::from_str(&env, "pool.fee");
+
+    client.grant(&alice, &backend, &prefix, &None);
+    client.set(&backend, &alice, &key, &1);
+
+    client.revoke(&alice, &backend, &prefix);
+    assert_eq!(client.get_grants(&alice, &backend).len(), 0);
+    client.set(&backend, &alice, &key, &2);
+}
+
+#[test]
+fn test_remove_many_is_atomic() {
+    let env = Env::default();
+    let (client, alice, backend) = setup(&env);
+    let fee = String::from_str(&env, "pool.fee");
+    let admin = String::from_str(&env, "admin");
+
+    client.set(&alice, &alice, &fee, &5);
+    client.set(&alice, &alice, &admin, &7);
+    client.grant(&alice, &backend, &String::from_str(&env, "pool."), &None);
+
+    // The first key is allowed, the second is not: nothing is removed.
+    let result = client.try_remove_many(&backend, &alice, &vec![&env, fee.clone(), admin.clone()]);
+    assert!(result.is_err());
+
+    assert_eq!(client.get(&alice, &fee), 5);
+    assert_eq!(client.get_version(&alice, &fee), 1);
+    assert_eq!(client.history(&alice, &fee, &10).len(), 1);
+    assert_eq!(client.get(&alice, &admin), 7);
+    assert_eq!(client.count(&alice), 2);
+}
+
+#[test]
+fn test_regrant_replaces_expiry_and_history_records_grantee() {
+    let env = Env::default();
+    let (client, alice, backend) = setup(&env);
+    let prefix = String::from_str(&env, "pool.");
+    let key = String::from_str(&env, "pool.fee");
+
+    client.grant(&alice, &backend, &prefix, &Some(10));
+    client.grant(&alice, &backend, &prefix, &None);
+
+    let grants = client.get_grants(&alice, &backend);
+    assert_eq!(grants.len(), 1);
+    assert_eq!(grants.get(0).unwrap().expires_at, None);
+
+    set_ledger(&env, 1, 1_000);
+    client.set(&backend, &alice, &key, &5);
+    assert_eq!(
+        client.history(&alice, &key, &1).get(0).unwrap().writer,
+        backend
+    );
+}
+
+#[test]
+#[should_panic(expected = "Error(Auth, InvalidAction)")]
+fn test_grant_requires_owner_auth() {
+    let env = Env::default();
+    let (client, alice, backend) = setup(&env);
+
+    env.set_auths(&[]);
+    client.grant(&alice, &backend, &String::from_str(&env, "pool."), &None);
+}
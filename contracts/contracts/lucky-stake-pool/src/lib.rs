@@ -6,23 +6,169 @@
 //! Deploy once per pool type with the appropriate `period_days` at initialization.
 //!
 //! Ticket formula: 1 ticket per $1 per day → tickets = amount * period_days
+//!
+//! Parameters can optionally be governed from a GeneralStore contract: see
+//! `set_config_source` and the `CONFIG_*` key names below.
 
 use soroban_sdk::{
-    contract, contractimpl, contracttype, log, token, Address, Env, Vec,
+    contract, contractclient, contracterror, contractimpl, contracttype, log, panic_with_error,
+    symbol_short, token, Address, Env, String, Vec,
 };
 
 #[contracttype]
 pub enum DataKey {
     Admin,
     Token,
-    PeriodDays,      // 7, 15, or 30
+    PeriodDays, // 7, 15, or 30
     Balance(Address),
     Tickets(Address),
+    Pending(Address),
+    Sponsorship(Address),
+    Delegate(Address),
+    Delegators(Address),
+    Delegations,
+    AutoCompound(Address),
+    LoyaltySince(Address),
     TotalDeposits,
     TotalTickets,
+    TotalSponsored,
     PrizeFund,
     Depositors,
     DrawNonce,       // Increments each draw for entropy
+    ConfigSource,    // Optional GeneralStore (store, owner) to read parameters from
+    ConfigCache,     // ConfigSnapshot read from the config source for the current round
+    FeeBps,          // Protocol fee taken from each prize, in basis points
+    Treasury,        // Only address the protocol fee can be paid out to
+    TreasuryBalance, // Protocol fees collected and not yet withdrawn
+    DepositLimits,   // Minimum deposit and TVL / per-user caps
+    RoundStart,      // Timestamp the current round started at
+    CutoffHours,     // Deposits this close to the round end wait for the next round
+    TotalPending,    // PendingTickets summed over all users
+    Draw(u64),       // DrawRecord per round (persistent)
+    Jackpot,         // Prize carried over from rounds that did not pay out
+    MinParticipants, // Fewest players with eligible tickets for a draw to pay out
+    LoyaltyStepBps,  // Multiplier added per consecutive round without a withdrawal
+    LoyaltyCapBps,   // Highest loyalty multiplier
+}
+
+/// Largest page `get_draws` returns.
+const MAX_PAGE_SIZE: u32 = 100;
+
+const SECONDS_PER_DAY: u64 = 86_400;
+const SECONDS_PER_HOUR: u64 = 3_600;
+
+/// Hard cap on the protocol fee: 20% of each prize.
+pub const MAX_FEE_BPS: u32 = 2_000;
+
+/// Loyalty multipliers are in basis points: 10_000 is 1x.
+pub const BASE_MULTIPLIER_BPS: u32 = 10_000;
+/// Hard cap on the loyalty multiplier: 3x.
+pub const MAX_LOYALTY_CAP_BPS: u32 = 30_000;
+
+// ─────────────────────────────────────────────
+//  Config registry (GeneralStore)
+// ─────────────────────────────────────────────
+
+/// GeneralStore key holding the draw period in days (7, 15, or 30).
+pub const CONFIG_PERIOD_DAYS: &str = "pool.period_days";
+/// GeneralStore key holding the protocol fee in basis points (≤ `MAX_FEE_BPS`).
+pub const CONFIG_FEE_BPS: &str = "pool.fee_bps";
+/// GeneralStore key holding the draw cutoff in hours (shorter than the period).
+pub const CONFIG_CUTOFF_HOURS: &str = "pool.cutoff_hours";
+/// GeneralStore key holding the minimum participants for a draw to pay out (≥ 1).
+pub const CONFIG_MIN_PARTICIPANTS: &str = "pool.min_participants";
+/// GeneralStore key holding the loyalty step in basis points per round.
+pub const CONFIG_LOYALTY_STEP_BPS: &str = "pool.loyalty_step_bps";
+/// GeneralStore key holding the loyalty cap in basis points (10_000 to `MAX_LOYALTY_CAP_BPS`).
+pub const CONFIG_LOYALTY_CAP_BPS: &str = "pool.loyalty_cap_bps";
+/// GeneralStore key holding the minimum deposit (≥ 0).
+pub const CONFIG_MIN_DEPOSIT: &str = "pool.min_deposit";
+/// GeneralStore key holding the TVL cap (≥ 0, 0 = unlimited).
+pub const CONFIG_MAX_TOTAL: &str = "pool.max_total";
+/// GeneralStore key holding the per-user cap (≥ 0, 0 = unlimited).
+pub const CONFIG_MAX_PER_USER: &str = "pool.max_per_user";
+
+/// The subset of the GeneralStore interface the pool reads from.
+#[contractclient(name = "ConfigStoreClient")]
+pub trait ConfigStore {
+    fn get_many(env: Env, owner: Address, keys: Vec<String>) -> Vec<Option<i128>>;
+}
+
+/// Where governed parameters live: `owner`'s namespace in the `store` contract.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConfigSource {
+    pub store: Address,
+    pub owner: Address,
+}
+
+/// Parameters in effect for one round. The config source is read at most
+/// once per round; anything missing or invalid there falls back to the
+/// admin-set value, which applies as soon as the admin sets it.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PoolConfig {
+    pub round: u64,
+    pub period_days: u32,
+    pub fee_bps: u32,
+    pub cutoff_hours: u32,
+    pub min_participants: u32,
+    pub loyalty_step_bps: u32,
+    pub loyalty_cap_bps: u32,
+    pub min_deposit: i128,
+    pub max_total: i128,
+    pub max_per_user: i128,
+}
+
+/// What the config source returned for `round`, one entry per `CONFIG_*` key.
+/// Kept so the store's values stay fixed for the whole round.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ConfigSnapshot {
+    pub round: u64,
+    pub values: Vec<Option<i128>>,
+}
+
+/// Tickets bought during the cutoff window of `round`. They sit out that
+/// round's draw and count as ordinary tickets from the next round on.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingTickets {
+    pub round: u64,
+    pub tickets: i128,
+}
+
+/// Risk limits checked on every deposit. A cap of 0 means unlimited.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DepositLimits {
+    pub min_deposit: i128,
+    pub max_total: i128,
+    pub max_per_user: i128,
+}
+
+/// What happened in one draw, kept on chain for prize history.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DrawRecord {
+    pub round: u64,
+    pub winners: Vec<Address>,
+    pub prize: i128,
+    pub total_tickets: i128, // eligible tickets the winning index was drawn from
+    pub winning_index: i128,
+    pub seed: u128, // timestamp, sequence and round combined
+    pub ledger_sequence: u32,
+    pub timestamp: u64,
+    pub jackpot: i128, // prize carried into the next round; 0 when paid out
+}
+
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum PoolError {
+    BelowMinDeposit = 1, // deposit is smaller than the minimum
+    PoolCapExceeded = 2, // deposit would push total principal over the TVL cap
+    UserCapExceeded = 3, // deposit would push the user's balance over the per-user cap
 }
 
 #[contract]
@@ -44,104 +190,36 @@ impl LuckyStakePool {
 
         env.storage().instance().set(&DataKey::Admin, &admin);
         env.storage().instance().set(&DataKey::Token, &token);
-        env.storage().instance().set(&DataKey::PeriodDays, &period_days);
-        env.storage().instance().set(&DataKey::TotalDeposits, &0i128);
+        env.storage()
+            .instance()
+            .set(&DataKey::PeriodDays, &period_days);
+        env.storage()
+            .instance()
+            .set(&DataKey::TotalDeposits, &0i128);
         env.storage().instance().set(&DataKey::TotalTickets, &0i128);
         env.storage().instance().set(&DataKey::PrizeFund, &0i128);
         env.storage().instance().set(&DataKey::DrawNonce, &0u64);
+        env.storage()
+            .instance()
+            .set(&DataKey::RoundStart, &env.ledger().timestamp());
 
         let empty: Vec<Address> = Vec::new(&env);
         env.storage().instance().set(&DataKey::Depositors, &empty);
 
-        log!(
-            &env,
-            "Pool initialized: period_days={}",
-            period_days
-        );
+        log!(&env, "Pool initialized: period_days={}", period_days);
     }
 
     /// User deposits tokens. Tickets = amount * period_days (1 ticket per $1 per day).
     pub fn deposit(env: Env, depositor: Address, amount: i128) {
         depositor.require_auth();
-        assert!(amount > 0, "deposit amount must be greater than zero");
-
-        let token_id: Address = env.storage().instance().get(&DataKey::Token).unwrap();
-        let token_client = token::Client::new(&env, &token_id);
-        token_client.transfer(
-            &depositor,
-            &env.current_contract_address(),
-            &amount,
-        );
-
-        let period_days: u32 = env.storage().instance().get(&DataKey::PeriodDays).unwrap();
-        let tickets_to_add = amount * (period_days as i128);
-
-        let current_balance: i128 = env
-            .storage()
-            .instance()
-            .get(&DataKey::Balance(depositor.clone()))
-            .unwrap_or(0);
-        let current_tickets: i128 = env
-            .storage()
-            .instance()
-            .get(&DataKey::Tickets(depositor.clone()))
-            .unwrap_or(0);
-
-        let new_balance = current_balance + amount;
-        let new_tickets = current_tickets + tickets_to_add;
-
-        env.storage()
-            .instance()
-            .set(&DataKey::Balance(depositor.clone()), &new_balance);
-        env.storage()
-            .instance()
-            .set(&DataKey::Tickets(depositor.clone()), &new_tickets);
-
-        let total: i128 = env
-            .storage()
-            .instance()
-            .get(&DataKey::TotalDeposits)
-            .unwrap();
-        let total_tickets: i128 = env
-            .storage()
-            .instance()
-            .get(&DataKey::TotalTickets)
-            .unwrap();
-
-        env.storage()
-            .instance()
-            .set(&DataKey::TotalDeposits, &(total + amount));
-        env.storage()
-            .instance()
-            .set(&DataKey::TotalTickets, &(total_tickets + tickets_to_add));
-
-        // Add to depositors list if new
-        let mut depositors: Vec<Address> = env
-            .storage()
-            .instance()
-            .get(&DataKey::Depositors)
-            .unwrap_or_else(|| Vec::new(&env));
-
-        let mut found = false;
-        for d in depositors.iter() {
-            if d == depositor {
-                found = true;
-                break;
-            }
-        }
-        if !found {
-            depositors.push_back(depositor.clone());
-            env.storage().instance().set(&DataKey::Depositors, &depositors);
-        }
+        Self::credit_deposit(&env, &depositor, &depositor, amount);
+    }
 
-        log!(
-            &env,
-            "Deposit: {} deposited {} | balance: {} | tickets: {}",
-            depositor,
-            amount,
-            new_balance,
-            new_tickets
-        );
+    /// `payer` funds a deposit credited to `beneficiary`: the balance and
+    /// tickets are the beneficiary's, and only the beneficiary can withdraw.
+    pub fn deposit_for(env: Env, payer: Address, beneficiary: Address, amount: i128) {
+        payer.require_auth();
+        Self::credit_deposit(&env, &payer, &beneficiary, amount);
     }
 
     /// User withdraws tokens. Tickets and balance decrease proportionally.
@@ -171,6 +249,8 @@ impl LuckyStakePool {
 
         let new_balance = balance - amount;
         let new_tickets = tickets - tickets_to_remove;
+        Self::burn_pending(&env, &depositor, tickets_to_remove);
+        Self::reset_loyalty(&env, &depositor, new_balance);
 
         env.storage()
             .instance()
@@ -179,8 +259,16 @@ impl LuckyStakePool {
             .instance()
             .set(&DataKey::Tickets(depositor.clone()), &new_tickets);
 
-        let total: i128 = env.storage().instance().get(&DataKey::TotalDeposits).unwrap();
-        let total_tickets: i128 = env.storage().instance().get(&DataKey::TotalTickets).unwrap();
+        let total: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::TotalDeposits)
+            .unwrap();
+        let total_tickets: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::TotalTickets)
+            .unwrap();
 
         env.storage()
             .instance()
@@ -206,7 +294,8 @@ impl LuckyStakePool {
         );
     }
 
-    /// Admin injects yield into the prize fund.
+    /// Admin injects yield into the prize fund. The protocol fee is set
+    /// aside for the treasury and the rest goes to the prize fund.
     pub fn add_prize(env: Env, amount: i128) {
         let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
         admin.require_auth();
@@ -219,158 +308,1389 @@ impl LuckyStakePool {
             &amount,
         );
 
+        let fee_bps = Self::round_config(&env).fee_bps;
+        let fee = amount * (fee_bps as i128) / 10_000;
+        let prize = amount - fee;
+
+        if fee > 0 {
+            let treasury: i128 = env
+                .storage()
+                .instance()
+                .get(&DataKey::TreasuryBalance)
+                .unwrap_or(0);
+            env.storage()
+                .instance()
+                .set(&DataKey::TreasuryBalance, &(treasury + fee));
+        }
+
         let current: i128 = env.storage().instance().get(&DataKey::PrizeFund).unwrap();
         env.storage()
             .instance()
-            .set(&DataKey::PrizeFund, &(current + amount));
+            .set(&DataKey::PrizeFund, &(current + prize));
 
-        log!(&env, "Prize fund topped up: {} | total: {}", amount, current + amount);
+        log!(
+            &env,
+            "Prize fund topped up: {} | fee: {} | total: {}",
+            prize,
+            fee,
+            current + prize
+        );
     }
 
-    /// Execute draw: select one random winner by ticket weight, transfer prize.
-    /// Uses Stellar ledger entropy (timestamp + sequence) as fallback for randomness.
-    pub fn execute_draw(env: Env) -> Address {
+    // ──────────────────────────────────────────
+    //  Jackpot
+    // ──────────────────────────────────────────
+
+    /// Admin sets how many players with eligible tickets a draw needs to pay
+    /// out. Players are counted per depositor, before delegated tickets are
+    /// merged into their delegatee. Below that the prize rolls over into the
+    /// jackpot.
+    pub fn set_min_participants(env: Env, min_participants: u32) {
         let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
         admin.require_auth();
+        assert!(min_participants >= 1, "min_participants must be at least 1");
 
-        let prize: i128 = env.storage().instance().get(&DataKey::PrizeFund).unwrap_or(0);
-        assert!(prize > 0, "no prize to distribute");
-
-        let total_tickets: i128 = env
-            .storage()
+        env.storage()
             .instance()
-            .get(&DataKey::TotalTickets)
-            .unwrap_or(0);
-        assert!(total_tickets > 0, "no tickets in pool");
+            .set(&DataKey::MinParticipants, &min_participants);
 
-        let depositors: Vec<Address> = env
-            .storage()
-            .instance()
-            .get(&DataKey::Depositors)
-            .unwrap_or_else(|| Vec::new(&env));
+        env.events().publish(
+            (symbol_short!("pool"), symbol_short!("min_part")),
+            min_participants,
+        );
+    }
 
-        // Build (address, tickets) for participants with tickets > 0
-        let mut participants: Vec<(Address, i128)> = Vec::new(&env);
-        let mut acc: i128 = 0;
-        for d in depositors.iter() {
-            let t: i128 = env
-                .storage()
-                .instance()
-                .get(&DataKey::Tickets(d.clone()))
-                .unwrap_or(0);
-            if t > 0 {
-                acc += t;
-                participants.push_back((d.clone(), t));
-            }
-        }
-        assert!(acc > 0, "no participants with tickets");
+    /// Prize carried over from earlier rounds; paid out with the next prize fund.
+    pub fn get_jackpot(env: Env) -> i128 {
+        env.storage().instance().get(&DataKey::Jackpot).unwrap_or(0)
+    }
 
-        // Randomness: Stellar block entropy (timestamp + sequence + nonce)
-        let ledger = env.ledger();
-        let timestamp = ledger.timestamp() as u128;
-        let sequence = ledger.sequence() as u128;
-        let nonce: u64 = env
+    /// What the next draw would pay out: this round's prize fund plus the jackpot.
+    pub fn get_total_prize(env: Env) -> i128 {
+        let prize_fund: i128 = env
             .storage()
             .instance()
-            .get(&DataKey::DrawNonce)
+            .get(&DataKey::PrizeFund)
             .unwrap_or(0);
-        let nonce_wide = nonce as u128;
+        prize_fund + Self::get_jackpot(env)
+    }
 
-        // Combine into a seed; use modulo for winning ticket index
-        let seed = timestamp
-            .wrapping_mul(31)
-            .wrapping_add(sequence)
-            .wrapping_mul(31)
-            .wrapping_add(nonce_wide);
-        let winning_ticket_index = (seed % (acc as u128)) as i128;
+    // ──────────────────────────────────────────
+    //  Draw history
+    // ──────────────────────────────────────────
 
-        // Find winner: iterate until accumulated tickets exceed winning index
-        let mut cumulative: i128 = 0;
-        let mut winner = participants.get(0).unwrap().0.clone();
-        for p in participants.iter() {
-            cumulative += p.1;
-            if winning_ticket_index < cumulative {
-                winner = p.0.clone();
-                break;
+    pub fn get_draw(env: Env, round: u64) -> Option<DrawRecord> {
+        env.storage().persistent().get(&DataKey::Draw(round))
+    }
+
+    /// Draws from round `from` onwards, oldest first, at most
+    /// `MAX_PAGE_SIZE` per call. Rounds whose record has expired are skipped.
+    pub fn get_draws(env: Env, from: u64, limit: u32) -> Vec<DrawRecord> {
+        let page = limit.min(MAX_PAGE_SIZE) as u64;
+        let end = Self::current_round(&env).min(from.saturating_add(page));
+        let mut draws = Vec::new(&env);
+        for round in from..end {
+            if let Some(record) = env.storage().persistent().get(&DataKey::Draw(round)) {
+                draws.push_back(record);
             }
         }
+        draws
+    }
 
-        // Transfer prize to winner
-        let token_id: Address = env.storage().instance().get(&DataKey::Token).unwrap();
-        token::Client::new(&env, &token_id).transfer(
-            &env.current_contract_address(),
-            &winner,
-            &prize,
+    // ──────────────────────────────────────────
+    //  Draw cutoff
+    // ──────────────────────────────────────────
+
+    /// Admin sets how many hours before the round end deposits stop counting
+    /// for the current draw. 0 disables the cutoff.
+    pub fn set_cutoff_hours(env: Env, cutoff_hours: u32) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        let period_days = Self::load_config(&env).period_days;
+        assert!(
+            cutoff_hours < period_days * 24,
+            "cutoff must be shorter than the period"
         );
 
-        env.storage().instance().set(&DataKey::PrizeFund, &0i128);
         env.storage()
             .instance()
-            .set(&DataKey::DrawNonce, &(nonce + 1));
+            .set(&DataKey::CutoffHours, &cutoff_hours);
 
-        log!(
-            &env,
-            "Draw executed: winner={} | prize={} | winning_ticket_index={}",
-            winner,
-            prize,
-            winning_ticket_index
+        env.events().publish(
+            (symbol_short!("pool"), symbol_short!("cutoff")),
+            cutoff_hours,
         );
-
-        winner
     }
 
-    // ──────────────────────────────────────────
-    //  Read helpers
-    // ──────────────────────────────────────────
+    /// Timestamp after which new deposits wait for the next round, or `None`
+    /// when no cutoff is configured.
+    pub fn get_cutoff_time(env: Env) -> Option<u64> {
+        Self::cutoff_time(&env, &Self::load_config(&env))
+    }
 
-    pub fn get_balance(env: Env, user: Address) -> i128 {
+    pub fn get_round_start(env: Env) -> u64 {
         env.storage()
             .instance()
-            .get(&DataKey::Balance(user))
+            .get(&DataKey::RoundStart)
             .unwrap_or(0)
     }
 
-    pub fn get_tickets(env: Env, user: Address) -> i128 {
-        env.storage()
-            .instance()
-            .get(&DataKey::Tickets(user))
-            .unwrap_or(0)
+    /// Tickets that take part in the next draw: all of `user`'s tickets
+    /// minus those bought during this round's cutoff window, with the
+    /// loyalty multiplier applied.
+    pub fn get_eligible_tickets(env: Env, user: Address) -> i128 {
+        Self::eligible_tickets(&env, &Self::load_config(&env), &user)
     }
 
-    pub fn get_total_deposits(env: Env) -> i128 {
-        env.storage()
-            .instance()
-            .get(&DataKey::TotalDeposits)
-            .unwrap_or(0)
+    /// Tickets `user` bought during this round's cutoff window.
+    pub fn get_pending_tickets(env: Env, user: Address) -> i128 {
+        let pending: Option<PendingTickets> = env.storage().instance().get(&DataKey::Pending(user));
+        Self::live_pending(&env, pending)
     }
 
-    pub fn get_total_tickets(env: Env) -> i128 {
-        env.storage()
-            .instance()
-            .get(&DataKey::TotalTickets)
-            .unwrap_or(0)
+    /// Tickets bought by all users during this round's cutoff window.
+    pub fn get_total_pending_tickets(env: Env) -> i128 {
+        let pending: Option<PendingTickets> = env.storage().instance().get(&DataKey::TotalPending);
+        Self::live_pending(&env, pending)
     }
 
-    pub fn get_prize_fund(env: Env) -> i128 {
-        env.storage()
+    /// Sum of every depositor's `get_eligible_tickets`, i.e. the draw's total weight.
+    pub fn get_total_eligible_tickets(env: Env) -> i128 {
+        let depositors: Vec<Address> = env
+            .storage()
             .instance()
-            .get(&DataKey::PrizeFund)
-            .unwrap_or(0)
+            .get(&DataKey::Depositors)
+            .unwrap_or_else(|| Vec::new(&env));
+        let config = Self::load_config(&env);
+        let mut total: i128 = 0;
+        for d in depositors.iter() {
+            total += Self::eligible_tickets(&env, &config, &d);
+        }
+        total
     }
 
-    pub fn get_period_days(env: Env) -> u32 {
+    // ──────────────────────────────────────────
+    //  Loyalty multiplier
+    // ──────────────────────────────────────────
+
+    /// Admin sets the loyalty multiplier: every consecutive round a user
+    /// goes without withdrawing adds `step_bps`, up to `cap_bps` in total.
+    /// A step of 0 (the default) turns the multiplier off.
+    pub fn set_loyalty(env: Env, step_bps: u32, cap_bps: u32) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        assert!(
+            (BASE_MULTIPLIER_BPS..=MAX_LOYALTY_CAP_BPS).contains(&cap_bps),
+            "loyalty cap out of range"
+        );
+        assert!(step_bps <= MAX_LOYALTY_CAP_BPS, "loyalty step out of range");
+
         env.storage()
             .instance()
-            .get(&DataKey::PeriodDays)
-            .unwrap_or(0)
+            .set(&DataKey::LoyaltyStepBps, &step_bps);
+        env.storage()
+            .instance()
+            .set(&DataKey::LoyaltyCapBps, &cap_bps);
+
+        env.events().publish(
+            (symbol_short!("pool"), symbol_short!("loyalty")),
+            (step_bps, cap_bps),
+        );
     }
 
-    pub fn get_admin(env: Env) -> Address {
-        env.storage().instance().get(&DataKey::Admin).unwrap()
+    /// `user`'s ticket multiplier in basis points (10_000 = 1x).
+    pub fn get_multiplier(env: Env, user: Address) -> u32 {
+        Self::multiplier(&env, &Self::load_config(&env), &user)
     }
 
-    pub fn get_token(env: Env) -> Address {
-        env.storage().instance().get(&DataKey::Token).unwrap()
+    /// Consecutive completed rounds `user` has held a deposit without withdrawing.
+    pub fn get_loyalty_rounds(env: Env, user: Address) -> u64 {
+        let since: Option<u64> = env.storage().instance().get(&DataKey::LoyaltySince(user));
+        since.map_or(0, |since| Self::current_round(&env).saturating_sub(since))
+    }
+
+    // ──────────────────────────────────────────
+    //  Auto-compound
+    // ──────────────────────────────────────────
+
+    /// When enabled, prizes `user` wins are added to their balance and
+    /// tickets instead of being transferred out. Deposit limits do not apply
+    /// to compounded prizes.
+    pub fn set_auto_compound(env: Env, user: Address, enabled: bool) {
+        user.require_auth();
+
+        if enabled {
+            env.storage()
+                .instance()
+                .set(&DataKey::AutoCompound(user.clone()), &true);
+        } else {
+            env.storage()
+                .instance()
+                .remove(&DataKey::AutoCompound(user.clone()));
+        }
+
+        log!(&env, "Auto-compound: {} set to {}", user, enabled);
+    }
+
+    pub fn get_auto_compound(env: Env, user: Address) -> bool {
+        env.storage()
+            .instance()
+            .get(&DataKey::AutoCompound(user))
+            .unwrap_or(false)
+    }
+
+    // ──────────────────────────────────────────
+    //  Delegation
+    // ──────────────────────────────────────────
+
+    /// Owner credits their draw weight for the current round to `delegatee`,
+    /// who receives the prize if those tickets win. Balance and withdrawals
+    /// stay with the owner, and the weight returns to them when the round
+    /// closes. Delegating again moves the weight to the new delegatee.
+    pub fn delegate_tickets(env: Env, owner: Address, delegatee: Address) {
+        owner.require_auth();
+        assert!(owner != delegatee, "cannot delegate to self");
+
+        if let Some(previous) = Self::get_delegate(env.clone(), owner.clone()) {
+            Self::remove_delegator(&env, &previous, &owner);
+        }
+        env.storage()
+            .instance()
+            .set(&DataKey::Delegate(owner.clone()), &delegatee);
+
+        let mut delegators = Self::get_delegators(env.clone(), delegatee.clone());
+        delegators.push_back(owner.clone());
+        env.storage()
+            .instance()
+            .set(&DataKey::Delegators(delegatee.clone()), &delegators);
+
+        let mut delegations = Self::delegations(&env);
+        if !delegations.contains(&owner) {
+            delegations.push_back(owner.clone());
+            env.storage()
+                .instance()
+                .set(&DataKey::Delegations, &delegations);
+        }
+
+        log!(
+            &env,
+            "Delegate: {} delegated tickets to {}",
+            owner,
+            delegatee
+        );
+    }
+
+    /// Owner takes their draw weight back.
+    pub fn undelegate(env: Env, owner: Address) {
+        owner.require_auth();
+
+        let delegatee =
+            Self::get_delegate(env.clone(), owner.clone()).expect("tickets not delegated");
+        Self::remove_delegator(&env, &delegatee, &owner);
+        env.storage()
+            .instance()
+            .remove(&DataKey::Delegate(owner.clone()));
+
+        let mut delegations = Self::delegations(&env);
+        if let Some(i) = delegations.first_index_of(&owner) {
+            delegations.remove(i);
+            env.storage()
+                .instance()
+                .set(&DataKey::Delegations, &delegations);
+        }
+
+        log!(
+            &env,
+            "Undelegate: {} took tickets back from {}",
+            owner,
+            delegatee
+        );
+    }
+
+    pub fn get_delegate(env: Env, owner: Address) -> Option<Address> {
+        env.storage().instance().get(&DataKey::Delegate(owner))
+    }
+
+    pub fn get_delegators(env: Env, delegatee: Address) -> Vec<Address> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Delegators(delegatee))
+            .unwrap_or_else(|| Vec::new(&env))
+    }
+
+    /// Eligible tickets other owners have delegated to `delegatee`.
+    pub fn get_delegated_weight(env: Env, delegatee: Address) -> i128 {
+        Self::delegated_weight(&env, &Self::load_config(&env), delegatee)
+    }
+
+    /// `user`'s weight in the next draw: their own eligible tickets unless
+    /// delegated away, plus whatever has been delegated to them.
+    pub fn get_draw_weight(env: Env, user: Address) -> i128 {
+        let config = Self::load_config(&env);
+        let own = if Self::get_delegate(env.clone(), user.clone()).is_some() {
+            0
+        } else {
+            Self::eligible_tickets(&env, &config, &user)
+        };
+        own + Self::delegated_weight(&env, &config, user)
+    }
+
+    // ──────────────────────────────────────────
+    //  Sponsorship
+    // ──────────────────────────────────────────
+
+    /// Sponsor adds principal that earns yield for the pool but takes no
+    /// tickets, so the sponsor never competes for prizes. Counts towards the
+    /// TVL cap; the minimum deposit and per-user cap do not apply.
+    pub fn sponsor(env: Env, sponsor: Address, amount: i128) {
+        sponsor.require_auth();
+        assert!(amount > 0, "sponsor amount must be greater than zero");
+
+        let limits = Self::deposit_limits(&Self::round_config(&env));
+        let principal = Self::get_total_principal(env.clone());
+        if limits.max_total > 0 && principal + amount > limits.max_total {
+            panic_with_error!(&env, PoolError::PoolCapExceeded);
+        }
+
+        let token_id: Address = env.storage().instance().get(&DataKey::Token).unwrap();
+        token::Client::new(&env, &token_id).transfer(
+            &sponsor,
+            &env.current_contract_address(),
+            &amount,
+        );
+
+        let current = Self::get_sponsorship(env.clone(), sponsor.clone());
+        let total = Self::get_total_sponsored(env.clone());
+        env.storage()
+            .instance()
+            .set(&DataKey::Sponsorship(sponsor.clone()), &(current + amount));
+        env.storage()
+            .instance()
+            .set(&DataKey::TotalSponsored, &(total + amount));
+
+        log!(
+            &env,
+            "Sponsor: {} added {} | sponsorship: {}",
+            sponsor,
+            amount,
+            current + amount
+        );
+    }
+
+    /// Sponsor takes back sponsored principal.
+    pub fn unsponsor(env: Env, sponsor: Address, amount: i128) {
+        sponsor.require_auth();
+        assert!(amount > 0, "unsponsor amount must be greater than zero");
+
+        let current = Self::get_sponsorship(env.clone(), sponsor.clone());
+        assert!(current >= amount, "insufficient sponsorship");
+
+        let total = Self::get_total_sponsored(env.clone());
+        if current == amount {
+            env.storage()
+                .instance()
+                .remove(&DataKey::Sponsorship(sponsor.clone()));
+        } else {
+            env.storage()
+                .instance()
+                .set(&DataKey::Sponsorship(sponsor.clone()), &(current - amount));
+        }
+        env.storage()
+            .instance()
+            .set(&DataKey::TotalSponsored, &(total - amount));
+
+        let token_id: Address = env.storage().instance().get(&DataKey::Token).unwrap();
+        token::Client::new(&env, &token_id).transfer(
+            &env.current_contract_address(),
+            &sponsor,
+            &amount,
+        );
+
+        log!(
+            &env,
+            "Unsponsor: {} removed {} | sponsorship: {}",
+            sponsor,
+            amount,
+            current - amount
+        );
+    }
+
+    pub fn get_sponsorship(env: Env, sponsor: Address) -> i128 {
+        env.storage()
+            .instance()
+            .get(&DataKey::Sponsorship(sponsor))
+            .unwrap_or(0)
+    }
+
+    pub fn get_total_sponsored(env: Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&DataKey::TotalSponsored)
+            .unwrap_or(0)
+    }
+
+    /// All yield-bearing principal: player deposits plus sponsorships.
+    pub fn get_total_principal(env: Env) -> i128 {
+        let total: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::TotalDeposits)
+            .unwrap_or(0);
+        total + Self::get_total_sponsored(env)
+    }
+
+    // ──────────────────────────────────────────
+    //  Deposit limits
+    // ──────────────────────────────────────────
+
+    /// Admin sets the minimum deposit and the TVL / per-user caps, unless
+    /// the config source governs them.
+    /// Pass 0 for a cap to leave it unlimited. Existing balances above a
+    /// lowered cap are left alone; only new deposits are checked.
+    pub fn set_deposit_limits(env: Env, min_deposit: i128, max_total: i128, max_per_user: i128) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        assert!(
+            min_deposit >= 0 && max_total >= 0 && max_per_user >= 0,
+            "limits must not be negative"
+        );
+        assert!(
+            max_per_user == 0 || min_deposit <= max_per_user,
+            "min_deposit exceeds max_per_user"
+        );
+
+        let limits = DepositLimits {
+            min_deposit,
+            max_total,
+            max_per_user,
+        };
+        env.storage()
+            .instance()
+            .set(&DataKey::DepositLimits, &limits);
+
+        env.events()
+            .publish((symbol_short!("pool"), symbol_short!("limits")), limits);
+    }
+
+    pub fn get_deposit_limits(env: Env) -> DepositLimits {
+        Self::deposit_limits(&Self::load_config(&env))
+    }
+
+    /// How much more the pool as a whole can take before hitting the TVL cap.
+    /// `i128::MAX` when uncapped.
+    pub fn get_remaining_capacity(env: Env) -> i128 {
+        let limits = Self::deposit_limits(&Self::load_config(&env));
+        if limits.max_total == 0 {
+            return i128::MAX;
+        }
+        (limits.max_total - Self::get_total_principal(env)).max(0)
+    }
+
+    /// How much more `user` can deposit right now: the smaller of the pool's
+    /// remaining capacity and the user's headroom under the per-user cap.
+    pub fn get_user_remaining_capacity(env: Env, user: Address) -> i128 {
+        let limits = Self::deposit_limits(&Self::load_config(&env));
+        let pool_remaining = Self::get_remaining_capacity(env.clone());
+        if limits.max_per_user == 0 {
+            return pool_remaining;
+        }
+        let balance: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::Balance(user))
+            .unwrap_or(0);
+        (limits.max_per_user - balance).max(0).min(pool_remaining)
+    }
+
+    // ──────────────────────────────────────────
+    //  Protocol fee
+    // ──────────────────────────────────────────
+
+    /// Admin sets the protocol fee, capped at `MAX_FEE_BPS`. While the
+    /// config source governs the fee its value stays in effect, so the
+    /// event reports the effective fee before and after the change.
+    pub fn set_fee_bps(env: Env, fee_bps: u32) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        assert!(fee_bps <= MAX_FEE_BPS, "fee exceeds maximum");
+
+        let old = Self::round_config(&env).fee_bps;
+        env.storage().instance().set(&DataKey::FeeBps, &fee_bps);
+        let new = Self::load_config(&env).fee_bps;
+
+        env.events().publish(
+            (symbol_short!("pool"), symbol_short!("fee_set")),
+            (old, new),
+        );
+    }
+
+    /// Admin sets the address protocol fees are paid out to.
+    pub fn set_treasury(env: Env, treasury: Address) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        env.storage().instance().set(&DataKey::Treasury, &treasury);
+
+        env.events()
+            .publish((symbol_short!("pool"), symbol_short!("treasury")), treasury);
+    }
+
+    /// Admin pays the collected protocol fees out to the treasury.
+    /// Returns the amount transferred.
+    pub fn withdraw_treasury(env: Env) -> i128 {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        let treasury: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Treasury)
+            .expect("treasury not set");
+        let balance: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::TreasuryBalance)
+            .unwrap_or(0);
+        assert!(balance > 0, "no fees to withdraw");
+
+        env.storage()
+            .instance()
+            .set(&DataKey::TreasuryBalance, &0i128);
+
+        let token_id: Address = env.storage().instance().get(&DataKey::Token).unwrap();
+        token::Client::new(&env, &token_id).transfer(
+            &env.current_contract_address(),
+            &treasury,
+            &balance,
+        );
+
+        env.events().publish(
+            (symbol_short!("pool"), symbol_short!("fee_paid"), treasury),
+            balance,
+        );
+
+        balance
+    }
+
+    /// Execute draw: select one random winner by ticket weight, transfer prize.
+    /// Uses Stellar ledger entropy (timestamp + sequence) as fallback for randomness.
+    /// Returns `None` when the round rolls over into the jackpot instead.
+    pub fn execute_draw(env: Env) -> Option<Address> {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        let prize_fund: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::PrizeFund)
+            .unwrap_or(0);
+        let jackpot: i128 = env.storage().instance().get(&DataKey::Jackpot).unwrap_or(0);
+        let prize = prize_fund + jackpot;
+        let config = Self::round_config(&env);
+
+        let depositors: Vec<Address> = env
+            .storage()
+            .instance()
+            .get(&DataKey::Depositors)
+            .unwrap_or_else(|| Vec::new(&env));
+
+        // Build (address, tickets) for participants with eligible tickets > 0,
+        // crediting delegated tickets to their delegatee. `players` counts the
+        // depositors themselves, so delegating does not shrink the head count.
+        let mut participants: Vec<(Address, i128)> = Vec::new(&env);
+        let mut players: u32 = 0;
+        let mut acc: i128 = 0;
+        for d in depositors.iter() {
+            let t = Self::eligible_tickets(&env, &config, &d);
+            if t > 0 {
+                players += 1;
+                acc += t;
+                let to = Self::get_delegate(env.clone(), d.clone()).unwrap_or(d);
+                match participants.iter().position(|p| p.0 == to) {
+                    Some(i) => {
+                        let (addr, weight) = participants.get(i as u32).unwrap();
+                        participants.set(i as u32, (addr, weight + t));
+                    }
+                    None => participants.push_back((to, t)),
+                }
+            }
+        }
+
+        // Randomness: Stellar block entropy (timestamp + sequence + nonce)
+        let ledger = env.ledger();
+        let timestamp = ledger.timestamp() as u128;
+        let sequence = ledger.sequence() as u128;
+        let nonce: u64 = env
+            .storage()
+            .instance()
+            .get(&DataKey::DrawNonce)
+            .unwrap_or(0);
+        let nonce_wide = nonce as u128;
+
+        // Combine into a seed; use modulo for winning ticket index
+        let seed = timestamp
+            .wrapping_mul(31)
+            .wrapping_add(sequence)
+            .wrapping_mul(31)
+            .wrapping_add(nonce_wide);
+
+        let mut record = DrawRecord {
+            round: nonce,
+            winners: Vec::new(&env),
+            prize: 0,
+            total_tickets: acc,
+            winning_index: 0,
+            seed,
+            ledger_sequence: ledger.sequence(),
+            timestamp: ledger.timestamp(),
+            jackpot: 0,
+        };
+
+        // Not enough players or nothing to win: close the round and carry
+        // whatever prize there is into the jackpot for the next one.
+        if prize == 0 || players < config.min_participants {
+            record.jackpot = prize;
+            env.storage().instance().set(&DataKey::Jackpot, &prize);
+            Self::close_round(&env, &record);
+
+            env.events().publish(
+                (symbol_short!("pool"), symbol_short!("rollover")),
+                (nonce, prize),
+            );
+            log!(
+                &env,
+                "Draw rolled over: players={} | jackpot={}",
+                players,
+                prize
+            );
+            return None;
+        }
+
+        let winning_ticket_index = (seed % (acc as u128)) as i128;
+
+        // Find winner: iterate until accumulated tickets exceed winning index
+        let mut cumulative: i128 = 0;
+        let mut winner = participants.get(0).unwrap().0.clone();
+        for p in participants.iter() {
+            cumulative += p.1;
+            if winning_ticket_index < cumulative {
+                winner = p.0.clone();
+                break;
+            }
+        }
+
+        // Transfer prize to winner, or keep it in the pool as their deposit
+        if Self::get_auto_compound(env.clone(), winner.clone()) {
+            Self::credit_position(&env, &winner, prize);
+        } else {
+            let token_id: Address = env.storage().instance().get(&DataKey::Token).unwrap();
+            token::Client::new(&env, &token_id).transfer(
+                &env.current_contract_address(),
+                &winner,
+                &prize,
+            );
+        }
+
+        record.winners.push_back(winner.clone());
+        record.prize = prize;
+        record.winning_index = winning_ticket_index;
+        env.storage().instance().set(&DataKey::Jackpot, &0i128);
+        Self::close_round(&env, &record);
+
+        log!(
+            &env,
+            "Draw executed: winner={} | prize={} | winning_ticket_index={}",
+            winner,
+            prize,
+            winning_ticket_index
+        );
+
+        Some(winner)
+    }
+
+    /// Admin points the pool at a GeneralStore namespace to read governed
+    /// parameters from. A round that has already read its parameters keeps
+    /// them until the next draw.
+    pub fn set_config_source(env: Env, store: Address, owner: Address) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        let source = ConfigSource {
+            store: store.clone(),
+            owner: owner.clone(),
+        };
+        env.storage()
+            .instance()
+            .set(&DataKey::ConfigSource, &source);
+
+        log!(&env, "Config source set: store={} owner={}", store, owner);
+    }
+
+    /// Admin stops reading parameters from GeneralStore, falling back to the
+    /// values set at `initialize` from the next round.
+    pub fn clear_config_source(env: Env) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+
+        env.storage().instance().remove(&DataKey::ConfigSource);
+
+        log!(&env, "Config source cleared");
+    }
+
+    // ──────────────────────────────────────────
+    //  Read helpers
+    // ──────────────────────────────────────────
+
+    pub fn get_config_source(env: Env) -> Option<ConfigSource> {
+        env.storage().instance().get(&DataKey::ConfigSource)
+    }
+
+    /// Parameters in effect for the current round.
+    pub fn get_config(env: Env) -> PoolConfig {
+        Self::load_config(&env)
+    }
+
+    pub fn get_balance(env: Env, user: Address) -> i128 {
+        env.storage()
+            .instance()
+            .get(&DataKey::Balance(user))
+            .unwrap_or(0)
+    }
+
+    /// `user`'s tickets with the loyalty multiplier applied.
+    pub fn get_tickets(env: Env, user: Address) -> i128 {
+        let tickets = Self::get_base_tickets(env.clone(), user.clone());
+        Self::apply_multiplier(tickets, Self::get_multiplier(env, user))
+    }
+
+    /// `user`'s tickets before the loyalty multiplier.
+    pub fn get_base_tickets(env: Env, user: Address) -> i128 {
+        env.storage()
+            .instance()
+            .get(&DataKey::Tickets(user))
+            .unwrap_or(0)
+    }
+
+    pub fn get_total_deposits(env: Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&DataKey::TotalDeposits)
+            .unwrap_or(0)
+    }
+
+    /// Total tickets before loyalty multipliers.
+    pub fn get_total_tickets(env: Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&DataKey::TotalTickets)
+            .unwrap_or(0)
+    }
+
+    pub fn get_prize_fund(env: Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&DataKey::PrizeFund)
+            .unwrap_or(0)
+    }
+
+    /// Protocol fee in effect for the current round.
+    pub fn get_fee_bps(env: Env) -> u32 {
+        Self::load_config(&env).fee_bps
+    }
+
+    pub fn get_treasury(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::Treasury)
+    }
+
+    pub fn get_treasury_balance(env: Env) -> i128 {
+        env.storage()
+            .instance()
+            .get(&DataKey::TreasuryBalance)
+            .unwrap_or(0)
+    }
+
+    pub fn get_period_days(env: Env) -> u32 {
+        if !env.storage().instance().has(&DataKey::PeriodDays) {
+            return 0;
+        }
+        Self::load_config(&env).period_days
+    }
+
+    pub fn get_admin(env: Env) -> Address {
+        env.storage().instance().get(&DataKey::Admin).unwrap()
+    }
+
+    pub fn get_token(env: Env) -> Address {
+        env.storage().instance().get(&DataKey::Token).unwrap()
+    }
+}
+
+// ─────────────────────────────────────────────
+//  Deposits
+// ─────────────────────────────────────────────
+
+impl LuckyStakePool {
+    /// Moves `amount` from `payer` into the pool and credits it to `depositor`.
+    fn credit_deposit(env: &Env, payer: &Address, depositor: &Address, amount: i128) {
+        assert!(amount > 0, "deposit amount must be greater than zero");
+        Self::check_limits(env, depositor, amount);
+
+        let token_id: Address = env.storage().instance().get(&DataKey::Token).unwrap();
+        let token_client = token::Client::new(env, &token_id);
+        token_client.transfer(payer, &env.current_contract_address(), &amount);
+
+        let old_balance = Self::get_balance(env.clone(), depositor.clone());
+        let round = Self::current_round(env);
+        Self::dilute_loyalty(env, depositor, round, old_balance, old_balance + amount);
+        let (new_balance, new_tickets) = Self::credit_position(env, depositor, amount);
+
+        log!(
+            env,
+            "Deposit: {} deposited {} (paid by {}) | balance: {} | tickets: {}",
+            depositor,
+            amount,
+            payer,
+            new_balance,
+            new_tickets
+        );
+    }
+
+    /// Adds `amount` to `depositor`'s balance with the matching tickets, for
+    /// tokens already held by the pool. Returns the new balance and tickets.
+    /// Only starts a loyalty streak that is not running: a compounded prize
+    /// was earned by the position, so it leaves the streak's age alone, and
+    /// fresh deposits are diluted by `credit_deposit` first.
+    fn credit_position(env: &Env, depositor: &Address, amount: i128) -> (i128, i128) {
+        let config = Self::round_config(env);
+        let tickets_to_add = amount * (config.period_days as i128);
+        if Self::in_cutoff(env, &config) {
+            Self::add_pending(env, depositor, config.round, tickets_to_add);
+        }
+
+        let current_balance: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::Balance(depositor.clone()))
+            .unwrap_or(0);
+        let current_tickets: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::Tickets(depositor.clone()))
+            .unwrap_or(0);
+
+        let new_balance = current_balance + amount;
+        let new_tickets = current_tickets + tickets_to_add;
+        Self::start_loyalty(env, depositor, config.round);
+
+        env.storage()
+            .instance()
+            .set(&DataKey::Balance(depositor.clone()), &new_balance);
+        env.storage()
+            .instance()
+            .set(&DataKey::Tickets(depositor.clone()), &new_tickets);
+
+        let total: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::TotalDeposits)
+            .unwrap();
+        let total_tickets: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::TotalTickets)
+            .unwrap();
+
+        env.storage()
+            .instance()
+            .set(&DataKey::TotalDeposits, &(total + amount));
+        env.storage()
+            .instance()
+            .set(&DataKey::TotalTickets, &(total_tickets + tickets_to_add));
+
+        // Add to depositors list if new
+        let mut depositors: Vec<Address> = env
+            .storage()
+            .instance()
+            .get(&DataKey::Depositors)
+            .unwrap_or_else(|| Vec::new(env));
+
+        let mut found = false;
+        for d in depositors.iter() {
+            if d == *depositor {
+                found = true;
+                break;
+            }
+        }
+        if !found {
+            depositors.push_back(depositor.clone());
+            env.storage()
+                .instance()
+                .set(&DataKey::Depositors, &depositors);
+        }
+
+        (new_balance, new_tickets)
+    }
+}
+
+// ─────────────────────────────────────────────
+//  Loyalty multiplier
+// ─────────────────────────────────────────────
+
+impl LuckyStakePool {
+    fn apply_multiplier(tickets: i128, multiplier_bps: u32) -> i128 {
+        tickets * (multiplier_bps as i128) / (BASE_MULTIPLIER_BPS as i128)
+    }
+
+    /// `get_multiplier` against an already resolved config, so loops over
+    /// depositors read the config once rather than once per user.
+    fn multiplier(env: &Env, config: &PoolConfig, user: &Address) -> u32 {
+        let since: Option<u64> = env
+            .storage()
+            .instance()
+            .get(&DataKey::LoyaltySince(user.clone()));
+        let Some(since) = since else {
+            return BASE_MULTIPLIER_BPS;
+        };
+        let rounds = Self::current_round(env).saturating_sub(since);
+        let bonus = rounds.saturating_mul(config.loyalty_step_bps as u64);
+        (BASE_MULTIPLIER_BPS as u64 + bonus).min(config.loyalty_cap_bps as u64) as u32
+    }
+
+    fn eligible_tickets(env: &Env, config: &PoolConfig, user: &Address) -> i128 {
+        let tickets: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::Tickets(user.clone()))
+            .unwrap_or(0);
+        let eligible = tickets - Self::get_pending_tickets(env.clone(), user.clone());
+        Self::apply_multiplier(eligible, Self::multiplier(env, config, user))
+    }
+
+    fn delegated_weight(env: &Env, config: &PoolConfig, delegatee: Address) -> i128 {
+        let mut weight: i128 = 0;
+        for owner in Self::get_delegators(env.clone(), delegatee).iter() {
+            weight += Self::eligible_tickets(env, config, &owner);
+        }
+        weight
+    }
+
+    /// New principal has no loyalty yet, so a top-up moves the streak start
+    /// forward to the balance-weighted age: adding as much as the user
+    /// already holds halves it. A first deposit starts the streak.
+    fn dilute_loyalty(env: &Env, user: &Address, round: u64, old_balance: i128, new_balance: i128) {
+        let key = DataKey::LoyaltySince(user.clone());
+        let since: Option<u64> = env.storage().instance().get(&key);
+        let since = match since {
+            Some(since) if old_balance > 0 => {
+                let age = round.saturating_sub(since) as i128;
+                round - (age * old_balance / new_balance) as u64
+            }
+            _ => round,
+        };
+        env.storage().instance().set(&key, &since);
+    }
+
+    /// Starts `user`'s streak at `round` unless one is already running.
+    fn start_loyalty(env: &Env, user: &Address, round: u64) {
+        let key = DataKey::LoyaltySince(user.clone());
+        if !env.storage().instance().has(&key) {
+            env.storage().instance().set(&key, &round);
+        }
+    }
+
+    /// A withdrawal restarts the streak; emptying the position ends it.
+    fn reset_loyalty(env: &Env, user: &Address, new_balance: i128) {
+        let key = DataKey::LoyaltySince(user.clone());
+        if new_balance == 0 {
+            env.storage().instance().remove(&key);
+        } else {
+            env.storage()
+                .instance()
+                .set(&key, &Self::current_round(env));
+        }
+    }
+}
+
+// ─────────────────────────────────────────────
+//  Delegation
+// ─────────────────────────────────────────────
+
+impl LuckyStakePool {
+    /// Owners with a delegation in the current round.
+    fn delegations(env: &Env) -> Vec<Address> {
+        env.storage()
+            .instance()
+            .get(&DataKey::Delegations)
+            .unwrap_or_else(|| Vec::new(env))
+    }
+
+    /// Delegations only cover the round they were made in: once it closes,
+    /// every owner's draw weight returns to them.
+    fn end_delegations(env: &Env) {
+        for owner in Self::delegations(env).iter() {
+            let key = DataKey::Delegate(owner);
+            let delegatee: Option<Address> = env.storage().instance().get(&key);
+            if let Some(delegatee) = delegatee {
+                env.storage()
+                    .instance()
+                    .remove(&DataKey::Delegators(delegatee));
+            }
+            env.storage().instance().remove(&key);
+        }
+        env.storage().instance().remove(&DataKey::Delegations);
+    }
+
+    fn remove_delegator(env: &Env, delegatee: &Address, owner: &Address) {
+        let key = DataKey::Delegators(delegatee.clone());
+        let mut delegators: Vec<Address> = env.storage().instance().get(&key).unwrap();
+        if let Some(i) = delegators.first_index_of(owner) {
+            delegators.remove(i);
+        }
+        if delegators.is_empty() {
+            env.storage().instance().remove(&key);
+        } else {
+            env.storage().instance().set(&key, &delegators);
+        }
+    }
+}
+
+// ─────────────────────────────────────────────
+//  Round lifecycle
+// ─────────────────────────────────────────────
+
+impl LuckyStakePool {
+    /// Stores the round's `DrawRecord` and starts the next round. Pending
+    /// tickets belong to the round just closed, so they are active from here on,
+    /// and the round's delegations end.
+    fn close_round(env: &Env, record: &DrawRecord) {
+        let draw_key = DataKey::Draw(record.round);
+        let max_ttl = env.storage().max_ttl();
+        env.storage().persistent().set(&draw_key, record);
+        env.storage()
+            .persistent()
+            .extend_ttl(&draw_key, max_ttl, max_ttl);
+
+        env.storage().instance().set(&DataKey::PrizeFund, &0i128);
+        env.storage()
+            .instance()
+            .set(&DataKey::DrawNonce, &(record.round + 1));
+        env.storage()
+            .instance()
+            .set(&DataKey::RoundStart, &env.ledger().timestamp());
+        Self::end_delegations(env);
+    }
+}
+
+// ─────────────────────────────────────────────
+//  Draw cutoff
+// ─────────────────────────────────────────────
+
+impl LuckyStakePool {
+    fn cutoff_time(env: &Env, config: &PoolConfig) -> Option<u64> {
+        if config.cutoff_hours == 0 {
+            return None;
+        }
+        let start: u64 = env.storage().instance().get(&DataKey::RoundStart)?;
+        let round_end = start + (config.period_days as u64) * SECONDS_PER_DAY;
+        Some(round_end.saturating_sub((config.cutoff_hours as u64) * SECONDS_PER_HOUR))
+    }
+
+    fn in_cutoff(env: &Env, config: &PoolConfig) -> bool {
+        Self::cutoff_time(env, config).is_some_and(|cutoff| env.ledger().timestamp() >= cutoff)
+    }
+
+    /// Pending tickets only hold back the round they were bought in.
+    fn live_pending(env: &Env, pending: Option<PendingTickets>) -> i128 {
+        match pending {
+            Some(p) if p.round == Self::current_round(env) => p.tickets,
+            _ => 0,
+        }
+    }
+
+    fn add_pending(env: &Env, user: &Address, round: u64, tickets: i128) {
+        let user_key = DataKey::Pending(user.clone());
+        for key in [user_key, DataKey::TotalPending] {
+            let pending: Option<PendingTickets> = env.storage().instance().get(&key);
+            let current = Self::live_pending(env, pending);
+            env.storage().instance().set(
+                &key,
+                &PendingTickets {
+                    round,
+                    tickets: current + tickets,
+                },
+            );
+        }
+    }
+
+    /// Withdrawals burn the most recent tickets first, i.e. pending ones.
+    fn burn_pending(env: &Env, user: &Address, burned: i128) {
+        let user_key = DataKey::Pending(user.clone());
+        let pending: Option<PendingTickets> = env.storage().instance().get(&user_key);
+        let current = Self::live_pending(env, pending);
+        if current == 0 {
+            env.storage().instance().remove(&user_key);
+            return;
+        }
+        let round = Self::current_round(env);
+        let cut = current.min(burned);
+        env.storage().instance().set(
+            &user_key,
+            &PendingTickets {
+                round,
+                tickets: current - cut,
+            },
+        );
+
+        let total: Option<PendingTickets> = env.storage().instance().get(&DataKey::TotalPending);
+        let total = Self::live_pending(env, total);
+        env.storage().instance().set(
+            &DataKey::TotalPending,
+            &PendingTickets {
+                round,
+                tickets: total - cut,
+            },
+        );
+    }
+}
+
+// ─────────────────────────────────────────────
+//  Deposit limits
+// ─────────────────────────────────────────────
+
+impl LuckyStakePool {
+    fn deposit_limits(config: &PoolConfig) -> DepositLimits {
+        DepositLimits {
+            min_deposit: config.min_deposit,
+            max_total: config.max_total,
+            max_per_user: config.max_per_user,
+        }
+    }
+
+    fn check_limits(env: &Env, depositor: &Address, amount: i128) {
+        let limits = Self::deposit_limits(&Self::round_config(env));
+        if amount < limits.min_deposit {
+            panic_with_error!(env, PoolError::BelowMinDeposit);
+        }
+        if limits.max_total > 0 {
+            let total = Self::get_total_principal(env.clone());
+            if total + amount > limits.max_total {
+                panic_with_error!(env, PoolError::PoolCapExceeded);
+            }
+        }
+        if limits.max_per_user > 0 {
+            let balance: i128 = env
+                .storage()
+                .instance()
+                .get(&DataKey::Balance(depositor.clone()))
+                .unwrap_or(0);
+            if balance + amount > limits.max_per_user {
+                panic_with_error!(env, PoolError::UserCapExceeded);
+            }
+        }
+    }
+}
+
+// ─────────────────────────────────────────────
+//  Config resolution
+// ─────────────────────────────────────────────
+
+impl LuckyStakePool {
+    fn current_round(env: &Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&DataKey::DrawNonce)
+            .unwrap_or(0)
+    }
+
+    /// Config for the current round, without writing anything. Uses the
+    /// round's snapshot of the config source if there is one, otherwise
+    /// reads the source afresh.
+    fn load_config(env: &Env) -> PoolConfig {
+        let round = Self::current_round(env);
+        let values = match Self::cached_values(env, round) {
+            Some(values) => values,
+            None => Self::read_source(env),
+        };
+        Self::resolve_config(env, round, &values)
+    }
+
+    /// Config for the current round, snapshotting the config source so the
+    /// store is only called once per round.
+    fn round_config(env: &Env) -> PoolConfig {
+        let round = Self::current_round(env);
+        let values = match Self::cached_values(env, round) {
+            Some(values) => values,
+            None => {
+                let values = Self::read_source(env);
+                let snapshot = ConfigSnapshot {
+                    round,
+                    values: values.clone(),
+                };
+                env.storage()
+                    .instance()
+                    .set(&DataKey::ConfigCache, &snapshot);
+                values
+            }
+        };
+        Self::resolve_config(env, round, &values)
+    }
+
+    fn cached_values(env: &Env, round: u64) -> Option<Vec<Option<i128>>> {
+        let snapshot: Option<ConfigSnapshot> = env.storage().instance().get(&DataKey::ConfigCache);
+        snapshot
+            .filter(|snapshot| snapshot.round == round)
+            .map(|snapshot| snapshot.values)
+    }
+
+    /// Every governed key's value in the config source, or nothing when no
+    /// source is set.
+    fn read_source(env: &Env) -> Vec<Option<i128>> {
+        let source: Option<ConfigSource> = env.storage().instance().get(&DataKey::ConfigSource);
+        let Some(source) = source else {
+            return Vec::new(env);
+        };
+
+        let keys = Vec::from_array(
+            env,
+            [
+                String::from_str(env, CONFIG_PERIOD_DAYS),
+                String::from_str(env, CONFIG_FEE_BPS),
+                String::from_str(env, CONFIG_CUTOFF_HOURS),
+                String::from_str(env, CONFIG_MIN_PARTICIPANTS),
+                String::from_str(env, CONFIG_LOYALTY_STEP_BPS),
+                String::from_str(env, CONFIG_LOYALTY_CAP_BPS),
+                String::from_str(env, CONFIG_MIN_DEPOSIT),
+                String::from_str(env, CONFIG_MAX_TOTAL),
+                String::from_str(env, CONFIG_MAX_PER_USER),
+            ],
+        );
+        Self::read_config_values(env, &source, &keys)
+    }
+
+    /// Reads `keys` from the config source. A key the store can't return as
+    /// an i128 (e.g. it holds another `Value` type) reads as missing, so it
+    /// falls back like any other invalid value instead of failing the pool.
+    fn read_config_values(
+        env: &Env,
+        source: &ConfigSource,
+        keys: &Vec<String>,
+    ) -> Vec<Option<i128>> {
+        let client = ConfigStoreClient::new(env, &source.store);
+        if let Ok(Ok(values)) = client.try_get_many(&source.owner, keys) {
+            return values;
+        }
+
+        // Some key failed: read them one by one so only the bad ones fall back.
+        let mut values = Vec::new(env);
+        for key in keys.iter() {
+            let single = Vec::from_array(env, [key]);
+            let value = match client.try_get_many(&source.owner, &single) {
+                Ok(Ok(read)) => read.get(0).flatten(),
+                _ => None,
+            };
+            values.push_back(value);
+        }
+        values
+    }
+
+    /// The admin-set parameters, overridden by every valid value the config
+    /// source returned.
+    fn resolve_config(env: &Env, round: u64, values: &Vec<Option<i128>>) -> PoolConfig {
+        let limits: DepositLimits = env
+            .storage()
+            .instance()
+            .get(&DataKey::DepositLimits)
+            .unwrap_or(DepositLimits {
+                min_deposit: 0,
+                max_total: 0,
+                max_per_user: 0,
+            });
+        let mut config = PoolConfig {
+            round,
+            period_days: env.storage().instance().get(&DataKey::PeriodDays).unwrap(),
+            fee_bps: env.storage().instance().get(&DataKey::FeeBps).unwrap_or(0),
+            cutoff_hours: env
+                .storage()
+                .instance()
+                .get(&DataKey::CutoffHours)
+                .unwrap_or(0),
+            min_participants: env
+                .storage()
+                .instance()
+                .get(&DataKey::MinParticipants)
+                .unwrap_or(1),
+            loyalty_step_bps: env
+                .storage()
+                .instance()
+                .get(&DataKey::LoyaltyStepBps)
+                .unwrap_or(0),
+            loyalty_cap_bps: env
+                .storage()
+                .instance()
+                .get(&DataKey::LoyaltyCapBps)
+                .unwrap_or(BASE_MULTIPLIER_BPS),
+            min_deposit: limits.min_deposit,
+            max_total: limits.max_total,
+            max_per_user: limits.max_per_user,
+        };
+
+        if let Some(Some(period_days)) = values.get(0) {
+            if period_days == 7 || period_days == 15 || period_days == 30 {
+                config.period_days = period_days as u32;
+            }
+        }
+        if let Some(Some(fee_bps)) = values.get(1) {
+            if (0..=MAX_FEE_BPS as i128).contains(&fee_bps) {
+                config.fee_bps = fee_bps as u32;
+            }
+        }
+        if let Some(Some(cutoff_hours)) = values.get(2) {
+            if (0..(config.period_days as i128) * 24).contains(&cutoff_hours) {
+                config.cutoff_hours = cutoff_hours as u32;
+            }
+        }
+        if let Some(Some(min_participants)) = values.get(3) {
+            if (1..=u32::MAX as i128).contains(&min_participants) {
+                config.min_participants = min_participants as u32;
+            }
+        }
+        if let Some(Some(step_bps)) = values.get(4) {
+            if (0..=MAX_LOYALTY_CAP_BPS as i128).contains(&step_bps) {
+                config.loyalty_step_bps = step_bps as u32;
+            }
+        }
+        if let Some(Some(cap_bps)) = values.get(5) {
+            if (BASE_MULTIPLIER_BPS as i128..=MAX_LOYALTY_CAP_BPS as i128).contains(&cap_bps) {
+                config.loyalty_cap_bps = cap_bps as u32;
+            }
+        }
+        if let Some(Some(min_deposit)) = values.get(6) {
+            if min_deposit >= 0 {
+                config.min_deposit = min_deposit;
+            }
+        }
+        if let Some(Some(max_total)) = values.get(7) {
+            if max_total >= 0 {
+                config.max_total = max_total;
+            }
+        }
+        if let Some(Some(max_per_user)) = values.get(8) {
+            if max_per_user >= 0 {
+                config.max_per_user = max_per_user;
+            }
+        }
+        // Limits that contradict each other fall back as a set.
+        if config.max_per_user > 0 && config.min_deposit > config.max_per_user {
+            config.min_deposit = limits.min_deposit;
+            config.max_total = limits.max_total;
+            config.max_per_user = limits.max_per_user;
+        }
+        // A cutoff set for a longer period may not fit a shorter governed one.
+        if config.cutoff_hours >= config.period_days * 24 {
+            config.cutoff_hours = 0;
+        }
+
+        config
     }
 }
 
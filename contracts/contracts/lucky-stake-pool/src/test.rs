@@ -2,14 +2,24 @@
 
 extern crate std;
 
-use soroban_sdk::{testutils::Address as _, Address, Env};
+use general_store::{GeneralStore, GeneralStoreClient, Value};
+use soroban_sdk::{
+    symbol_short,
+    testutils::{Address as _, Events, Ledger},
+    Address, Env, IntoVal, String, Vec,
+};
 
-use crate::{LuckyStakePool, LuckyStakePoolClient};
+use crate::{
+    LuckyStakePool, LuckyStakePoolClient, PoolError, CONFIG_FEE_BPS, CONFIG_MAX_PER_USER,
+    CONFIG_MAX_TOTAL, CONFIG_MIN_DEPOSIT, CONFIG_PERIOD_DAYS,
+};
 
-fn setup(env: &Env, period_days: u32) -> (LuckyStakePoolClient, Address, Address, Address) {
+fn setup(env: &Env, period_days: u32) -> (LuckyStakePoolClient<'_>, Address, Address, Address) {
     let admin = Address::generate(env);
     let user1 = Address::generate(env);
-    let token = env.register_stellar_asset_contract(admin.clone());
+    let token = env
+        .register_stellar_asset_contract_v2(admin.clone())
+        .address();
 
     let contract_id = env.register_contract(None, LuckyStakePool);
     let client = LuckyStakePoolClient::new(env, &contract_id);
@@ -52,7 +62,9 @@ fn test_initialize_monthly() {
 fn test_initialize_invalid_period() {
     let env = Env::default();
     let admin = Address::generate(&env);
-    let token = env.register_stellar_asset_contract(admin.clone());
+    let token = env
+        .register_stellar_asset_contract_v2(admin.clone())
+        .address();
     let contract_id = env.register_contract(None, LuckyStakePool);
     let client = LuckyStakePoolClient::new(&env, &contract_id);
     env.mock_all_auths();
@@ -108,6 +120,1014 @@ fn test_add_prize_and_draw() {
     assert_eq!(client.get_prize_fund(), 100);
 
     let winner = client.execute_draw();
-    assert_eq!(winner, user);
+    assert_eq!(winner, Some(user));
     assert_eq!(client.get_prize_fund(), 0);
 }
+
+fn setup_config_store<'a>(
+    env: &'a Env,
+    client: &LuckyStakePoolClient,
+) -> (GeneralStoreClient<'a>, Address) {
+    let store_id = env.register_contract(None, GeneralStore);
+    let store = GeneralStoreClient::new(env, &store_id);
+    let governance = Address::generate(env);
+    client.set_config_source(&store_id, &governance);
+    (store, governance)
+}
+
+#[test]
+fn test_config_source_overrides_period() {
+    let env = Env::default();
+    let (client, _contract, token, user) = setup(&env, 7);
+    let (store, governance) = setup_config_store(&env, &client);
+
+    let key = String::from_str(&env, CONFIG_PERIOD_DAYS);
+    store.set(&governance, &governance, &key, &30);
+
+    assert_eq!(client.get_period_days(), 30);
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    token_admin.mint(&user, &1_000);
+    client.deposit(&user, &100);
+    assert_eq!(client.get_tickets(&user), 3_000);
+}
+
+#[test]
+fn test_config_is_cached_for_the_round() {
+    let env = Env::default();
+    let (client, _contract, token, user) = setup(&env, 7);
+    let (store, governance) = setup_config_store(&env, &client);
+    let key = String::from_str(&env, CONFIG_PERIOD_DAYS);
+    store.set(&governance, &governance, &key, &15);
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    let admin = client.get_admin();
+    token_admin.mint(&admin, &1_000);
+    token_admin.mint(&user, &1_000);
+
+    client.deposit(&user, &100);
+    assert_eq!(client.get_config().period_days, 15);
+
+    // A mid-round change does not apply until the next round, even when an
+    // admin setter runs in between.
+    store.set(&governance, &governance, &key, &30);
+    client.set_fee_bps(&100);
+    client.deposit(&user, &100);
+    assert_eq!(client.get_tickets(&user), 3_000);
+    assert_eq!(client.get_config().round, 0);
+    assert_eq!(client.get_fee_bps(), 100);
+
+    client.add_prize(&100);
+    client.execute_draw();
+
+    assert_eq!(client.get_config().round, 1);
+    assert_eq!(client.get_period_days(), 30);
+}
+
+#[test]
+fn test_invalid_or_missing_config_falls_back() {
+    let env = Env::default();
+    let (client, _contract, _token, _user) = setup(&env, 7);
+    let (store, governance) = setup_config_store(&env, &client);
+
+    // Nothing set yet: the initialize value applies.
+    assert_eq!(client.get_period_days(), 7);
+
+    let key = String::from_str(&env, CONFIG_PERIOD_DAYS);
+    store.set(&governance, &governance, &key, &12);
+    assert_eq!(client.get_period_days(), 7);
+
+    client.clear_config_source();
+    assert_eq!(client.get_config_source(), None);
+}
+
+#[test]
+fn test_non_i128_config_value_falls_back() {
+    let env = Env::default();
+    let (client, _contract, token, user) = setup(&env, 7);
+    let (store, governance) = setup_config_store(&env, &client);
+
+    // A governed key holding another value type must not take the pool down.
+    store.set_value(
+        &governance,
+        &governance,
+        &String::from_str(&env, CONFIG_PERIOD_DAYS),
+        &Value::U64(30),
+    );
+    store.set(
+        &governance,
+        &governance,
+        &String::from_str(&env, CONFIG_FEE_BPS),
+        &1_000,
+    );
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    token_admin.mint(&client.get_admin(), &1_000);
+    token_admin.mint(&user, &1_000);
+
+    assert_eq!(client.get_period_days(), 7);
+    client.deposit(&user, &100);
+    assert_eq!(client.get_tickets(&user), 700);
+
+    // The other keys are still read.
+    client.add_prize(&100);
+    assert_eq!(client.get_fee_bps(), 1_000);
+    assert_eq!(client.get_prize_fund(), 90);
+    assert_eq!(client.execute_draw(), Some(user));
+}
+
+#[test]
+fn test_protocol_fee_split() {
+    let env = Env::default();
+    let (client, contract, token, user) = setup(&env, 7);
+    let treasury = Address::generate(&env);
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    let admin = client.get_admin();
+    token_admin.mint(&admin, &10_000);
+    token_admin.mint(&user, &1_000);
+
+    client.set_fee_bps(&1_000);
+    client.set_treasury(&treasury);
+    assert_eq!(client.get_fee_bps(), 1_000);
+    assert_eq!(client.get_treasury(), Some(treasury.clone()));
+
+    client.deposit(&user, &500);
+    client.add_prize(&1_000);
+
+    assert_eq!(client.get_prize_fund(), 900);
+    assert_eq!(client.get_treasury_balance(), 100);
+
+    let token_client = soroban_sdk::token::TokenClient::new(&env, &token);
+    assert_eq!(client.withdraw_treasury(), 100);
+    assert_eq!(token_client.balance(&treasury), 100);
+    assert_eq!(client.get_treasury_balance(), 0);
+
+    // The draw pays out the prize net of fees; principal stays in the pool.
+    client.execute_draw();
+    assert_eq!(token_client.balance(&user), 500 + 900);
+    assert_eq!(token_client.balance(&contract), 500);
+}
+
+#[test]
+#[should_panic(expected = "fee exceeds maximum")]
+fn test_fee_above_cap_rejected() {
+    let env = Env::default();
+    let (client, _contract, _token, _user) = setup(&env, 7);
+
+    client.set_fee_bps(&2_001);
+}
+
+#[test]
+#[should_panic(expected = "treasury not set")]
+fn test_withdraw_treasury_requires_treasury() {
+    let env = Env::default();
+    let (client, _contract, token, _user) = setup(&env, 7);
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    token_admin.mint(&client.get_admin(), &1_000);
+    client.set_fee_bps(&500);
+    client.add_prize(&1_000);
+
+    client.withdraw_treasury();
+}
+
+#[test]
+fn test_fee_change_publishes_event() {
+    let env = Env::default();
+    let (client, _contract, _token, _user) = setup(&env, 7);
+
+    client.set_fee_bps(&250);
+
+    let (_id, topics, data) = env.events().all().last().unwrap();
+    assert_eq!(
+        topics,
+        (symbol_short!("pool"), symbol_short!("fee_set")).into_val(&env)
+    );
+    let (old, new): (u32, u32) = data.into_val(&env);
+    assert_eq!((old, new), (0, 250));
+}
+
+#[test]
+fn test_config_source_overrides_fee() {
+    let env = Env::default();
+    let (client, _contract, token, _user) = setup(&env, 7);
+    let (store, governance) = setup_config_store(&env, &client);
+    store.set(
+        &governance,
+        &governance,
+        &String::from_str(&env, CONFIG_FEE_BPS),
+        &300,
+    );
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    token_admin.mint(&client.get_admin(), &1_000);
+    client.add_prize(&1_000);
+
+    assert_eq!(client.get_fee_bps(), 300);
+    assert_eq!(client.get_treasury_balance(), 30);
+}
+
+#[test]
+fn test_fee_event_reports_governed_fee() {
+    let env = Env::default();
+    let (client, _contract, _token, _user) = setup(&env, 7);
+    let (store, governance) = setup_config_store(&env, &client);
+    store.set(
+        &governance,
+        &governance,
+        &String::from_str(&env, CONFIG_FEE_BPS),
+        &300,
+    );
+
+    // The governed fee stays in effect, and the event says so.
+    client.set_fee_bps(&500);
+
+    let (_id, _topics, data) = env.events().all().last().unwrap();
+    let (old, new): (u32, u32) = data.into_val(&env);
+    assert_eq!((old, new), (300, 300));
+    assert_eq!(client.get_fee_bps(), 300);
+}
+
+#[test]
+fn test_deposit_limits_enforced() {
+    let env = Env::default();
+    let (client, _contract, token, user1) = setup(&env, 7);
+    let user2 = Address::generate(&env);
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    token_admin.mint(&user1, &10_000);
+    token_admin.mint(&user2, &10_000);
+
+    client.set_deposit_limits(&10, &1_000, &600);
+    let limits = client.get_deposit_limits();
+    assert_eq!(
+        (limits.min_deposit, limits.max_total, limits.max_per_user),
+        (10, 1_000, 600)
+    );
+
+    assert_eq!(
+        client.try_deposit(&user1, &9),
+        Err(Ok(PoolError::BelowMinDeposit.into()))
+    );
+
+    client.deposit(&user1, &500);
+    assert_eq!(
+        client.try_deposit(&user1, &101),
+        Err(Ok(PoolError::UserCapExceeded.into()))
+    );
+    client.deposit(&user1, &100);
+
+    client.deposit(&user2, &300);
+    assert_eq!(
+        client.try_deposit(&user2, &101),
+        Err(Ok(PoolError::PoolCapExceeded.into()))
+    );
+    client.deposit(&user2, &100);
+
+    assert_eq!(client.get_total_deposits(), 1_000);
+    assert_eq!(client.get_remaining_capacity(), 0);
+}
+
+#[test]
+fn test_remaining_capacity_views() {
+    let env = Env::default();
+    let (client, _contract, token, user1) = setup(&env, 7);
+
+    // No limits configured: unlimited.
+    assert_eq!(client.get_remaining_capacity(), i128::MAX);
+    assert_eq!(client.get_user_remaining_capacity(&user1), i128::MAX);
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    token_admin.mint(&user1, &1_000);
+
+    client.set_deposit_limits(&0, &1_000, &400);
+    client.deposit(&user1, &300);
+
+    assert_eq!(client.get_remaining_capacity(), 700);
+    assert_eq!(client.get_user_remaining_capacity(&user1), 100);
+
+    // Per-user headroom is bounded by what the pool has left.
+    client.set_deposit_limits(&0, &350, &400);
+    assert_eq!(client.get_user_remaining_capacity(&user1), 50);
+
+    // Withdrawing frees capacity again.
+    client.withdraw(&user1, &300);
+    assert_eq!(client.get_remaining_capacity(), 350);
+    assert_eq!(client.get_user_remaining_capacity(&user1), 350);
+}
+
+#[test]
+fn test_config_source_overrides_deposit_limits() {
+    let env = Env::default();
+    let (client, _contract, token, user) = setup(&env, 7);
+    client.set_deposit_limits(&1, &10_000, &5_000);
+
+    let (store, governance) = setup_config_store(&env, &client);
+    store.set(
+        &governance,
+        &governance,
+        &String::from_str(&env, CONFIG_MIN_DEPOSIT),
+        &50,
+    );
+    store.set(
+        &governance,
+        &governance,
+        &String::from_str(&env, CONFIG_MAX_TOTAL),
+        &300,
+    );
+    // Negative: invalid, so the admin-set per-user cap stays.
+    store.set(
+        &governance,
+        &governance,
+        &String::from_str(&env, CONFIG_MAX_PER_USER),
+        &-1,
+    );
+
+    let limits = client.get_deposit_limits();
+    assert_eq!(
+        (limits.min_deposit, limits.max_total, limits.max_per_user),
+        (50, 300, 5_000)
+    );
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    token_admin.mint(&user, &1_000);
+    assert_eq!(
+        client.try_deposit(&user, &49),
+        Err(Ok(PoolError::BelowMinDeposit.into()))
+    );
+    assert_eq!(
+        client.try_deposit(&user, &301),
+        Err(Ok(PoolError::PoolCapExceeded.into()))
+    );
+    client.deposit(&user, &300);
+}
+
+#[test]
+fn test_contradictory_governed_limits_fall_back() {
+    let env = Env::default();
+    let (client, _contract, _token, _user) = setup(&env, 7);
+    client.set_deposit_limits(&10, &0, &1_000);
+
+    let (store, governance) = setup_config_store(&env, &client);
+    store.set(
+        &governance,
+        &governance,
+        &String::from_str(&env, CONFIG_MIN_DEPOSIT),
+        &500,
+    );
+    store.set(
+        &governance,
+        &governance,
+        &String::from_str(&env, CONFIG_MAX_PER_USER),
+        &100,
+    );
+
+    let limits = client.get_deposit_limits();
+    assert_eq!(
+        (limits.min_deposit, limits.max_total, limits.max_per_user),
+        (10, 0, 1_000)
+    );
+}
+
+#[test]
+#[should_panic(expected = "min_deposit exceeds max_per_user")]
+fn test_min_deposit_above_per_user_cap_rejected() {
+    let env = Env::default();
+    let (client, _contract, _token, _user) = setup(&env, 7);
+
+    client.set_deposit_limits(&200, &0, &100);
+}
+
+#[test]
+fn test_deposit_in_cutoff_is_pending_until_next_round() {
+    let env = Env::default();
+    let (client, _contract, token, user1) = setup(&env, 7);
+    let user2 = Address::generate(&env);
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    token_admin.mint(&client.get_admin(), &1_000);
+    token_admin.mint(&user1, &1_000);
+    token_admin.mint(&user2, &1_000);
+
+    client.set_cutoff_hours(&24);
+    let start = client.get_round_start();
+    assert_eq!(client.get_cutoff_time(), Some(start + 6 * 86_400));
+
+    client.deposit(&user1, &100);
+    assert_eq!(client.get_eligible_tickets(&user1), 700);
+
+    // Into the last day of the round: the deposit waits for the next round.
+    env.ledger()
+        .with_mut(|l| l.timestamp = start + 6 * 86_400 + 1);
+    client.deposit(&user2, &1_000);
+    assert_eq!(client.get_tickets(&user2), 7_000);
+    assert_eq!(client.get_pending_tickets(&user2), 7_000);
+    assert_eq!(client.get_eligible_tickets(&user2), 0);
+    assert_eq!(client.get_total_eligible_tickets(), 700);
+
+    client.add_prize(&100);
+    assert_eq!(client.execute_draw(), Some(user1));
+
+    // The next round counts them in full.
+    assert_eq!(client.get_pending_tickets(&user2), 0);
+    assert_eq!(client.get_eligible_tickets(&user2), 7_000);
+    assert_eq!(client.get_total_eligible_tickets(), 7_700);
+    assert_eq!(client.get_round_start(), start + 6 * 86_400 + 1);
+}
+
+#[test]
+fn test_withdraw_burns_pending_tickets_first() {
+    let env = Env::default();
+    let (client, _contract, token, user) = setup(&env, 7);
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    token_admin.mint(&user, &1_000);
+
+    client.set_cutoff_hours(&24);
+    client.deposit(&user, &300);
+
+    let start = client.get_round_start();
+    env.ledger().with_mut(|l| l.timestamp = start + 6 * 86_400);
+    client.deposit(&user, &100);
+    assert_eq!(client.get_pending_tickets(&user), 700);
+
+    client.withdraw(&user, &200);
+    assert_eq!(client.get_tickets(&user), 1_400);
+    assert_eq!(client.get_pending_tickets(&user), 0);
+    assert_eq!(client.get_eligible_tickets(&user), 1_400);
+    assert_eq!(client.get_total_eligible_tickets(), 1_400);
+}
+
+#[test]
+#[should_panic(expected = "cutoff must be shorter than the period")]
+fn test_cutoff_longer_than_period_rejected() {
+    let env = Env::default();
+    let (client, _contract, _token, _user) = setup(&env, 7);
+
+    client.set_cutoff_hours(&(7 * 24));
+}
+
+#[test]
+fn test_draw_history_recorded() {
+    let env = Env::default();
+    let (client, _contract, token, user) = setup(&env, 7);
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    token_admin.mint(&client.get_admin(), &1_000);
+    token_admin.mint(&user, &1_000);
+
+    client.deposit(&user, &100);
+    assert_eq!(client.get_draw(&0), None);
+
+    env.ledger().with_mut(|l| {
+        l.timestamp = 12_345;
+        l.sequence_number = 42;
+    });
+    client.add_prize(&250);
+    let winner = client.execute_draw().unwrap();
+
+    let record = client.get_draw(&0).unwrap();
+    assert_eq!(record.round, 0);
+    assert_eq!(record.winners, Vec::from_array(&env, [winner]));
+    assert_eq!(record.prize, 250);
+    assert_eq!(record.total_tickets, 700);
+    assert_eq!(record.seed, (12_345u128 * 31 + 42) * 31);
+    assert_eq!(record.winning_index, (record.seed % 700) as i128);
+    assert_eq!(record.ledger_sequence, 42);
+    assert_eq!(record.timestamp, 12_345);
+    assert_eq!(record.jackpot, 0);
+}
+
+#[test]
+fn test_get_draws_paginates() {
+    let env = Env::default();
+    let (client, _contract, token, user) = setup(&env, 7);
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    token_admin.mint(&client.get_admin(), &1_000);
+    token_admin.mint(&user, &1_000);
+    client.deposit(&user, &100);
+
+    for prize in 1..=5 {
+        client.add_prize(&(prize * 10));
+        client.execute_draw();
+    }
+
+    let page = client.get_draws(&1, &3);
+    assert_eq!(page.len(), 3);
+    assert_eq!(page.get(0).unwrap().round, 1);
+    assert_eq!(page.get(2).unwrap().prize, 40);
+
+    // The last page stops at the most recent draw.
+    let tail = client.get_draws(&3, &10);
+    assert_eq!(tail.len(), 2);
+    assert_eq!(tail.get(1).unwrap().round, 4);
+    assert_eq!(client.get_draws(&5, &10).len(), 0);
+}
+
+#[test]
+fn test_empty_round_rolls_prize_into_jackpot() {
+    let env = Env::default();
+    let (client, _contract, token, user) = setup(&env, 7);
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    token_admin.mint(&client.get_admin(), &1_000);
+    token_admin.mint(&user, &1_000);
+
+    // No tickets: the round closes and the prize carries over.
+    client.add_prize(&100);
+    assert_eq!(client.execute_draw(), None);
+    assert_eq!(client.get_prize_fund(), 0);
+    assert_eq!(client.get_jackpot(), 100);
+    assert_eq!(client.get_draw(&0).unwrap().jackpot, 100);
+
+    // No prize either: still closes cleanly.
+    assert_eq!(client.execute_draw(), None);
+    assert_eq!(client.get_config().round, 2);
+
+    // The jackpot grows with the next round's prize and pays out in full.
+    client.add_prize(&50);
+    assert_eq!(client.get_total_prize(), 150);
+    client.deposit(&user, &100);
+    assert_eq!(client.execute_draw(), Some(user.clone()));
+
+    let token_client = soroban_sdk::token::TokenClient::new(&env, &token);
+    assert_eq!(token_client.balance(&user), 900 + 150);
+    assert_eq!(client.get_jackpot(), 0);
+    assert_eq!(client.get_draw(&2).unwrap().prize, 150);
+}
+
+#[test]
+fn test_min_participants_rolls_over() {
+    let env = Env::default();
+    let (client, _contract, token, user1) = setup(&env, 7);
+    let user2 = Address::generate(&env);
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    token_admin.mint(&client.get_admin(), &1_000);
+    token_admin.mint(&user1, &1_000);
+    token_admin.mint(&user2, &1_000);
+
+    client.set_min_participants(&2);
+    client.deposit(&user1, &100);
+    client.add_prize(&100);
+
+    assert_eq!(client.execute_draw(), None);
+    assert_eq!(client.get_jackpot(), 100);
+
+    let (_id, topics, data) = env.events().all().last().unwrap();
+    assert_eq!(
+        topics,
+        (symbol_short!("pool"), symbol_short!("rollover")).into_val(&env)
+    );
+    let (round, jackpot): (u64, i128) = data.into_val(&env);
+    assert_eq!((round, jackpot), (0, 100));
+
+    client.deposit(&user2, &100);
+    assert!(client.execute_draw().is_some());
+    assert_eq!(client.get_jackpot(), 0);
+}
+
+#[test]
+fn test_sponsorship_adds_principal_without_tickets() {
+    let env = Env::default();
+    let (client, contract, token, user) = setup(&env, 7);
+    let sponsor = Address::generate(&env);
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    token_admin.mint(&client.get_admin(), &1_000);
+    token_admin.mint(&user, &1_000);
+    token_admin.mint(&sponsor, &10_000);
+
+    client.deposit(&user, &100);
+    client.sponsor(&sponsor, &5_000);
+
+    assert_eq!(client.get_sponsorship(&sponsor), 5_000);
+    assert_eq!(client.get_tickets(&sponsor), 0);
+    assert_eq!(client.get_balance(&sponsor), 0);
+    assert_eq!(client.get_total_sponsored(), 5_000);
+    assert_eq!(client.get_total_deposits(), 100);
+    assert_eq!(client.get_total_principal(), 5_100);
+    assert_eq!(client.get_total_tickets(), 700);
+
+    // The sponsor can never win.
+    client.add_prize(&100);
+    assert_eq!(client.execute_draw(), Some(user));
+
+    client.unsponsor(&sponsor, &2_000);
+    assert_eq!(client.get_sponsorship(&sponsor), 3_000);
+    assert_eq!(client.get_total_sponsored(), 3_000);
+
+    let token_client = soroban_sdk::token::TokenClient::new(&env, &token);
+    assert_eq!(token_client.balance(&sponsor), 7_000);
+    assert_eq!(token_client.balance(&contract), 3_100);
+}
+
+#[test]
+fn test_sponsorship_counts_towards_tvl_cap() {
+    let env = Env::default();
+    let (client, _contract, token, user) = setup(&env, 7);
+    let sponsor = Address::generate(&env);
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    token_admin.mint(&user, &1_000);
+    token_admin.mint(&sponsor, &1_000);
+
+    client.set_deposit_limits(&0, &1_000, &0);
+    client.sponsor(&sponsor, &800);
+    assert_eq!(client.get_remaining_capacity(), 200);
+
+    assert_eq!(
+        client.try_deposit(&user, &201),
+        Err(Ok(PoolError::PoolCapExceeded.into()))
+    );
+    assert_eq!(
+        client.try_sponsor(&sponsor, &201),
+        Err(Ok(PoolError::PoolCapExceeded.into()))
+    );
+    client.deposit(&user, &200);
+}
+
+#[test]
+#[should_panic(expected = "insufficient sponsorship")]
+fn test_unsponsor_more_than_sponsored() {
+    let env = Env::default();
+    let (client, _contract, token, _user) = setup(&env, 7);
+    let sponsor = Address::generate(&env);
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    token_admin.mint(&sponsor, &1_000);
+
+    client.sponsor(&sponsor, &100);
+    client.unsponsor(&sponsor, &101);
+}
+
+#[test]
+fn test_deposit_for_credits_beneficiary() {
+    let env = Env::default();
+    let (client, _contract, token, parent) = setup(&env, 7);
+    let child = Address::generate(&env);
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    token_admin.mint(&parent, &1_000);
+
+    client.deposit_for(&parent, &child, &300);
+
+    assert_eq!(client.get_balance(&child), 300);
+    assert_eq!(client.get_tickets(&child), 2_100);
+    assert_eq!(client.get_balance(&parent), 0);
+    assert_eq!(client.get_tickets(&parent), 0);
+
+    // Withdrawals pay the beneficiary, not the payer.
+    client.withdraw(&child, &100);
+    let token_client = soroban_sdk::token::TokenClient::new(&env, &token);
+    assert_eq!(token_client.balance(&child), 100);
+    assert_eq!(token_client.balance(&parent), 700);
+}
+
+#[test]
+#[should_panic(expected = "insufficient balance")]
+fn test_payer_cannot_withdraw_beneficiary_deposit() {
+    let env = Env::default();
+    let (client, _contract, token, parent) = setup(&env, 7);
+    let child = Address::generate(&env);
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    token_admin.mint(&parent, &1_000);
+
+    client.deposit_for(&parent, &child, &300);
+    client.withdraw(&parent, &300);
+}
+
+#[test]
+fn test_deposit_for_requires_payer_auth_only() {
+    let env = Env::default();
+    let (client, _contract, token, parent) = setup(&env, 7);
+    let child = Address::generate(&env);
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    token_admin.mint(&parent, &1_000);
+
+    client.deposit_for(&parent, &child, &300);
+
+    let auths = env.auths();
+    assert_eq!(auths.len(), 1);
+    assert_eq!(auths[0].0, parent);
+}
+
+#[test]
+fn test_delegated_tickets_win_for_delegatee() {
+    let env = Env::default();
+    let (client, contract, token, owner) = setup(&env, 7);
+    let charity = Address::generate(&env);
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    token_admin.mint(&client.get_admin(), &1_000);
+    token_admin.mint(&owner, &1_000);
+
+    client.deposit(&owner, &100);
+    client.delegate_tickets(&owner, &charity);
+
+    assert_eq!(client.get_delegate(&owner), Some(charity.clone()));
+    assert_eq!(
+        client.get_delegators(&charity),
+        Vec::from_array(&env, [owner.clone()])
+    );
+    assert_eq!(client.get_delegated_weight(&charity), 700);
+    assert_eq!(client.get_draw_weight(&charity), 700);
+    assert_eq!(client.get_draw_weight(&owner), 0);
+    assert_eq!(client.get_tickets(&owner), 700);
+
+    client.add_prize(&100);
+    assert_eq!(client.execute_draw(), Some(charity.clone()));
+
+    let token_client = soroban_sdk::token::TokenClient::new(&env, &token);
+    assert_eq!(token_client.balance(&charity), 100);
+
+    // Principal stays with the owner.
+    client.withdraw(&owner, &100);
+    assert_eq!(token_client.balance(&owner), 1_000);
+    assert_eq!(token_client.balance(&contract), 0);
+}
+
+#[test]
+fn test_delegated_weight_is_merged_per_delegatee() {
+    let env = Env::default();
+    let (client, _contract, token, user1) = setup(&env, 7);
+    let user2 = Address::generate(&env);
+    let charity = Address::generate(&env);
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    token_admin.mint(&client.get_admin(), &1_000);
+    token_admin.mint(&user1, &1_000);
+    token_admin.mint(&user2, &1_000);
+
+    client.deposit(&user1, &100);
+    client.deposit(&user2, &200);
+    client.delegate_tickets(&user1, &charity);
+    client.delegate_tickets(&user2, &charity);
+    assert_eq!(client.get_delegated_weight(&charity), 2_100);
+
+    // Two owners delegating to one address still count as two players.
+    client.set_min_participants(&2);
+    client.add_prize(&100);
+    assert_eq!(client.execute_draw(), Some(charity.clone()));
+
+    // Delegations lapse with the round, so renew them for the next one.
+    client.set_min_participants(&3);
+    client.delegate_tickets(&user1, &charity);
+    client.delegate_tickets(&user2, &charity);
+    client.add_prize(&100);
+    assert_eq!(client.execute_draw(), None);
+    assert_eq!(client.get_jackpot(), 100);
+}
+
+#[test]
+fn test_undelegate_and_redelegate() {
+    let env = Env::default();
+    let (client, _contract, token, owner) = setup(&env, 7);
+    let first = Address::generate(&env);
+    let second = Address::generate(&env);
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    token_admin.mint(&owner, &1_000);
+    client.deposit(&owner, &100);
+
+    client.delegate_tickets(&owner, &first);
+    client.delegate_tickets(&owner, &second);
+    assert_eq!(client.get_delegators(&first).len(), 0);
+    assert_eq!(client.get_delegated_weight(&second), 700);
+
+    client.undelegate(&owner);
+    assert_eq!(client.get_delegate(&owner), None);
+    assert_eq!(client.get_delegated_weight(&second), 0);
+    assert_eq!(client.get_draw_weight(&owner), 700);
+}
+
+#[test]
+#[should_panic(expected = "tickets not delegated")]
+fn test_undelegate_without_delegation() {
+    let env = Env::default();
+    let (client, _contract, _token, owner) = setup(&env, 7);
+
+    client.undelegate(&owner);
+}
+
+#[test]
+fn test_auto_compound_credits_prize_to_balance() {
+    let env = Env::default();
+    let (client, contract, token, user) = setup(&env, 7);
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    token_admin.mint(&client.get_admin(), &1_000);
+    token_admin.mint(&user, &1_000);
+
+    client.deposit(&user, &500);
+    client.set_auto_compound(&user, &true);
+    assert!(client.get_auto_compound(&user));
+
+    client.add_prize(&100);
+    assert_eq!(client.execute_draw(), Some(user.clone()));
+
+    // The prize stays in the pool as part of the winner's deposit.
+    let token_client = soroban_sdk::token::TokenClient::new(&env, &token);
+    assert_eq!(token_client.balance(&user), 500);
+    assert_eq!(token_client.balance(&contract), 600);
+    assert_eq!(client.get_balance(&user), 600);
+    assert_eq!(client.get_tickets(&user), 4_200);
+    assert_eq!(client.get_total_deposits(), 600);
+    assert_eq!(client.get_total_tickets(), 4_200);
+
+    client.withdraw(&user, &600);
+    assert_eq!(token_client.balance(&user), 1_100);
+}
+
+#[test]
+fn test_auto_compound_can_be_turned_off() {
+    let env = Env::default();
+    let (client, _contract, token, user) = setup(&env, 7);
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    token_admin.mint(&client.get_admin(), &1_000);
+    token_admin.mint(&user, &1_000);
+
+    client.deposit(&user, &500);
+    client.set_auto_compound(&user, &true);
+    client.set_auto_compound(&user, &false);
+    assert!(!client.get_auto_compound(&user));
+
+    client.add_prize(&100);
+    client.execute_draw();
+
+    let token_client = soroban_sdk::token::TokenClient::new(&env, &token);
+    assert_eq!(token_client.balance(&user), 600);
+    assert_eq!(client.get_balance(&user), 500);
+}
+
+#[test]
+fn test_loyalty_multiplier_grows_and_caps() {
+    let env = Env::default();
+    let (client, _contract, token, user) = setup(&env, 7);
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    token_admin.mint(&user, &1_000);
+
+    // +10% per round, capped at 1.25x.
+    client.set_loyalty(&1_000, &12_500);
+    client.deposit(&user, &100);
+    assert_eq!(client.get_multiplier(&user), 10_000);
+    assert_eq!(client.get_tickets(&user), 700);
+
+    client.execute_draw();
+    assert_eq!(client.get_loyalty_rounds(&user), 1);
+    assert_eq!(client.get_multiplier(&user), 11_000);
+    assert_eq!(client.get_tickets(&user), 770);
+    assert_eq!(client.get_base_tickets(&user), 700);
+    assert_eq!(client.get_eligible_tickets(&user), 770);
+
+    client.execute_draw();
+    client.execute_draw();
+    assert_eq!(client.get_multiplier(&user), 12_500);
+    assert_eq!(client.get_tickets(&user), 875);
+    assert_eq!(client.get_total_eligible_tickets(), 875);
+    assert_eq!(client.get_total_tickets(), 700);
+}
+
+#[test]
+fn test_withdrawal_resets_loyalty() {
+    let env = Env::default();
+    let (client, _contract, token, user) = setup(&env, 7);
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    token_admin.mint(&user, &1_000);
+
+    client.set_loyalty(&1_000, &20_000);
+    client.deposit(&user, &100);
+    client.execute_draw();
+    client.execute_draw();
+    assert_eq!(client.get_multiplier(&user), 12_000);
+
+    // Doubling the balance halves the streak.
+    client.deposit(&user, &100);
+    assert_eq!(client.get_loyalty_rounds(&user), 1);
+    assert_eq!(client.get_multiplier(&user), 11_000);
+
+    client.withdraw(&user, &50);
+    assert_eq!(client.get_loyalty_rounds(&user), 0);
+    assert_eq!(client.get_multiplier(&user), 10_000);
+    assert_eq!(client.get_tickets(&user), 1_050);
+
+    client.execute_draw();
+    assert_eq!(client.get_multiplier(&user), 11_000);
+}
+
+#[test]
+fn test_large_top_up_does_not_inherit_loyalty() {
+    let env = Env::default();
+    let (client, _contract, token, user) = setup(&env, 7);
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    token_admin.mint(&user, &100_000);
+
+    client.set_loyalty(&1_000, &15_000);
+    client.deposit(&user, &10);
+    for _ in 0..5 {
+        client.execute_draw();
+    }
+    assert_eq!(client.get_multiplier(&user), 15_000);
+
+    // A dust position held for rounds does not boost a fresh large deposit.
+    client.deposit(&user, &10_000);
+    assert_eq!(client.get_loyalty_rounds(&user), 0);
+    assert_eq!(client.get_multiplier(&user), 10_000);
+    assert_eq!(client.get_tickets(&user), 70_070);
+
+    // The streak then grows again from the new balance.
+    client.execute_draw();
+    assert_eq!(client.get_multiplier(&user), 11_000);
+}
+
+#[test]
+fn test_compounded_prize_keeps_loyalty() {
+    let env = Env::default();
+    let (client, _contract, token, user) = setup(&env, 7);
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    token_admin.mint(&client.get_admin(), &1_000);
+    token_admin.mint(&user, &1_000);
+
+    client.set_loyalty(&1_000, &15_000);
+    client.deposit(&user, &100);
+    client.set_auto_compound(&user, &true);
+    client.execute_draw();
+    client.execute_draw();
+    assert_eq!(client.get_loyalty_rounds(&user), 2);
+
+    // A prize ten times the balance is compounded without diluting the streak.
+    client.add_prize(&1_000);
+    assert_eq!(client.execute_draw(), Some(user.clone()));
+    assert_eq!(client.get_balance(&user), 1_100);
+    assert_eq!(client.get_loyalty_rounds(&user), 3);
+    assert_eq!(client.get_multiplier(&user), 13_000);
+}
+
+#[test]
+fn test_loyalty_boosts_draw_weight() {
+    let env = Env::default();
+    let (client, _contract, token, loyal) = setup(&env, 7);
+    let newcomer = Address::generate(&env);
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    token_admin.mint(&client.get_admin(), &1_000);
+    token_admin.mint(&loyal, &1_000);
+    token_admin.mint(&newcomer, &1_000);
+
+    client.set_loyalty(&5_000, &20_000);
+    client.deposit(&loyal, &100);
+    client.execute_draw();
+    client.execute_draw();
+    client.deposit(&newcomer, &100);
+
+    assert_eq!(client.get_eligible_tickets(&loyal), 1_400);
+    assert_eq!(client.get_eligible_tickets(&newcomer), 700);
+
+    client.add_prize(&100);
+    client.execute_draw();
+    let record = client.get_draw(&2).unwrap();
+    assert_eq!(record.total_tickets, 2_100);
+}
+
+#[test]
+#[should_panic(expected = "loyalty cap out of range")]
+fn test_loyalty_cap_above_max_rejected() {
+    let env = Env::default();
+    let (client, _contract, _token, _user) = setup(&env, 7);
+
+    client.set_loyalty(&1_000, &30_001);
+}
+
+#[test]
+fn test_delegation_ends_with_the_round() {
+    let env = Env::default();
+    let (client, _contract, token, owner) = setup(&env, 7);
+    let charity = Address::generate(&env);
+
+    let token_admin = soroban_sdk::token::StellarAssetClient::new(&env, &token);
+    token_admin.mint(&client.get_admin(), &1_000);
+    token_admin.mint(&owner, &1_000);
+
+    client.deposit(&owner, &100);
+    client.delegate_tickets(&owner, &charity);
+    client.add_prize(&100);
+    assert_eq!(client.execute_draw(), Some(charity.clone()));
+
+    // The next round's weight is the owner's again.
+    assert_eq!(client.get_delegate(&owner), None);
+    assert_eq!(client.get_delegators(&charity).len(), 0);
+    assert_eq!(client.get_delegated_weight(&charity), 0);
+    assert_eq!(client.get_draw_weight(&owner), 700);
+
+    client.add_prize(&100);
+    assert_eq!(client.execute_draw(), Some(owner));
+}
@@ -10,8 +10,17 @@ pub enum DataKey {
     Balance(Address),
     TotalDeposits,
     Depositors,
+    StreamRate,     // tokens per second, scaled by REWARD_PRECISION
+    StreamEnd,      // timestamp at which the active stream finishes
+    LastAccrual,    // timestamp of the last reward_per_share update
+    RewardPerShare, // cumulative rewards per deposited token, scaled
+    RewardPerSharePaid(Address),
+    PendingRewards(Address),
 }
 
+/// Fixed-point scale applied to the stream rate and reward-per-share values.
+const REWARD_PRECISION: i128 = 1_000_000_000_000;
+
 #[contract]
 pub struct PrizePoolContract;
 
@@ -30,6 +39,96 @@ impl PrizePoolContract {
             .set(&DataKey::TotalDeposits, &0i128);
         let empty: Vec<Address> = Vec::new(&env);
         env.storage().instance().set(&DataKey::Depositors, &empty);
+        env.storage().instance().set(&DataKey::StreamRate, &0i128);
+        env.storage().instance().set(&DataKey::StreamEnd, &0u64);
+        env.storage()
+            .instance()
+            .set(&DataKey::LastAccrual, &env.ledger().timestamp());
+        env.storage()
+            .instance()
+            .set(&DataKey::RewardPerShare, &0i128);
+    }
+
+    /// User deposits tokens and starts accruing streamed rewards.
+    pub fn deposit(env: Env, depositor: Address, amount: i128) {
+        depositor.require_auth();
+        assert!(amount > 0, "deposit amount must be greater than zero");
+
+        let token_id: Address = env.storage().instance().get(&DataKey::Token).unwrap();
+        token::Client::new(&env, &token_id).transfer(
+            &depositor,
+            &env.current_contract_address(),
+            &amount,
+        );
+
+        // Settle rewards earned on the old balance before it changes.
+        Self::checkpoint(&env, &depositor);
+
+        let balance: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::Balance(depositor.clone()))
+            .unwrap_or(0);
+        env.storage()
+            .instance()
+            .set(&DataKey::Balance(depositor.clone()), &(balance + amount));
+
+        let total: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::TotalDeposits)
+            .unwrap();
+        env.storage()
+            .instance()
+            .set(&DataKey::TotalDeposits, &(total + amount));
+
+        let mut depositors: Vec<Address> =
+            env.storage().instance().get(&DataKey::Depositors).unwrap();
+        if !depositors.contains(&depositor) {
+            depositors.push_back(depositor.clone());
+            env.storage()
+                .instance()
+                .set(&DataKey::Depositors, &depositors);
+        }
+
+        log!(&env, "Deposit: {} deposited {}", depositor, amount);
+    }
+
+    /// User withdraws principal. Rewards accrued so far stay claimable.
+    pub fn withdraw(env: Env, depositor: Address, amount: i128) {
+        depositor.require_auth();
+        assert!(amount > 0, "withdraw amount must be greater than zero");
+
+        let balance: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::Balance(depositor.clone()))
+            .unwrap_or(0);
+        assert!(balance >= amount, "insufficient balance");
+
+        Self::checkpoint(&env, &depositor);
+
+        env.storage()
+            .instance()
+            .set(&DataKey::Balance(depositor.clone()), &(balance - amount));
+
+        let total: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::TotalDeposits)
+            .unwrap();
+        env.storage()
+            .instance()
+            .set(&DataKey::TotalDeposits, &(total - amount));
+
+        let token_id: Address = env.storage().instance().get(&DataKey::Token).unwrap();
+        token::Client::new(&env, &token_id).transfer(
+            &env.current_contract_address(),
+            &depositor,
+            &amount,
+        );
+
+        log!(&env, "Withdraw: {} withdrew {}", depositor, amount);
     }
 
     /// Admin injects yield into the prize fund.
@@ -58,6 +157,77 @@ impl PrizePoolContract {
         );
     }
 
+    /// Admin streams `amount` to depositors linearly over `duration` seconds.
+    /// Any unreleased part of a running stream is folded into the new one.
+    pub fn add_prize_stream(env: Env, amount: i128, duration: u64) {
+        let admin: Address = env.storage().instance().get(&DataKey::Admin).unwrap();
+        admin.require_auth();
+        assert!(amount > 0, "stream amount must be greater than zero");
+        assert!(duration > 0, "stream duration must be greater than zero");
+
+        let token_id: Address = env.storage().instance().get(&DataKey::Token).unwrap();
+        token::Client::new(&env, &token_id).transfer(
+            &admin,
+            &env.current_contract_address(),
+            &amount,
+        );
+
+        Self::accrue(&env);
+
+        let now = env.ledger().timestamp();
+        let rate: i128 = env.storage().instance().get(&DataKey::StreamRate).unwrap();
+        let end: u64 = env.storage().instance().get(&DataKey::StreamEnd).unwrap();
+        let leftover = if end > now {
+            rate * ((end - now) as i128)
+        } else {
+            0
+        };
+
+        let new_rate = (amount * REWARD_PRECISION + leftover) / (duration as i128);
+        let new_end = now + duration;
+        env.storage()
+            .instance()
+            .set(&DataKey::StreamRate, &new_rate);
+        env.storage().instance().set(&DataKey::StreamEnd, &new_end);
+
+        log!(
+            &env,
+            "Prize stream started: {} over {}s | ends at: {}",
+            amount,
+            duration,
+            new_end
+        );
+    }
+
+    /// Transfers a user's accrued stream rewards to them.
+    pub fn claim_rewards(env: Env, user: Address) -> i128 {
+        user.require_auth();
+
+        Self::checkpoint(&env, &user);
+
+        let pending: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::PendingRewards(user.clone()))
+            .unwrap_or(0);
+        if pending > 0 {
+            env.storage()
+                .instance()
+                .set(&DataKey::PendingRewards(user.clone()), &0i128);
+
+            let token_id: Address = env.storage().instance().get(&DataKey::Token).unwrap();
+            token::Client::new(&env, &token_id).transfer(
+                &env.current_contract_address(),
+                &user,
+                &pending,
+            );
+
+            log!(&env, "{} claimed stream rewards: {}", user, pending);
+        }
+
+        pending
+    }
+
     /// Distributes prize fund proportionally to all depositors.
     pub fn distribute(env: Env) {
         let prize: i128 = env.storage().instance().get(&DataKey::PrizeFund).unwrap();
@@ -103,6 +273,19 @@ impl PrizePoolContract {
             .unwrap_or(0)
     }
 
+    /// Rewards streamed to `user` so far that have not been claimed yet.
+    pub fn get_pending_rewards(env: Env, user: Address) -> i128 {
+        let (reward_per_share, _) = Self::current_reward_per_share(&env);
+        Self::earned(&env, &user, reward_per_share)
+    }
+
+    pub fn get_stream_end(env: Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&DataKey::StreamEnd)
+            .unwrap_or(0)
+    }
+
     pub fn get_user_share_bp(env: Env, user: Address) -> u64 {
         let total: i128 = env
             .storage()
@@ -120,3 +303,108 @@ impl PrizePoolContract {
         ((balance * 10_000) / total) as u64
     }
 }
+
+// ─────────────────────────────────────────────
+//  Stream accounting
+// ─────────────────────────────────────────────
+
+impl PrizePoolContract {
+    /// Reward-per-share as of now, plus the amount released while nobody
+    /// was deposited (which has no one to accrue to).
+    fn current_reward_per_share(env: &Env) -> (i128, i128) {
+        let reward_per_share: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::RewardPerShare)
+            .unwrap_or(0);
+        let last: u64 = env
+            .storage()
+            .instance()
+            .get(&DataKey::LastAccrual)
+            .unwrap_or(0);
+        let end: u64 = env
+            .storage()
+            .instance()
+            .get(&DataKey::StreamEnd)
+            .unwrap_or(0);
+        let applicable = env.ledger().timestamp().min(end);
+        if applicable <= last {
+            return (reward_per_share, 0);
+        }
+
+        let rate: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::StreamRate)
+            .unwrap_or(0);
+        let released = rate * ((applicable - last) as i128);
+        let total: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::TotalDeposits)
+            .unwrap_or(0);
+        if total == 0 {
+            return (reward_per_share, released / REWARD_PRECISION);
+        }
+        (reward_per_share + released / total, 0)
+    }
+
+    /// Brings reward_per_share up to the current ledger timestamp. Rewards
+    /// released with no depositors present are moved into the prize fund.
+    fn accrue(env: &Env) {
+        let (reward_per_share, unassigned) = Self::current_reward_per_share(env);
+        env.storage()
+            .instance()
+            .set(&DataKey::RewardPerShare, &reward_per_share);
+        env.storage()
+            .instance()
+            .set(&DataKey::LastAccrual, &env.ledger().timestamp());
+
+        if unassigned > 0 {
+            let fund: i128 = env.storage().instance().get(&DataKey::PrizeFund).unwrap();
+            env.storage()
+                .instance()
+                .set(&DataKey::PrizeFund, &(fund + unassigned));
+        }
+    }
+
+    fn earned(env: &Env, user: &Address, reward_per_share: i128) -> i128 {
+        let balance: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::Balance(user.clone()))
+            .unwrap_or(0);
+        let paid: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::RewardPerSharePaid(user.clone()))
+            .unwrap_or(0);
+        let pending: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::PendingRewards(user.clone()))
+            .unwrap_or(0);
+        pending + balance * (reward_per_share - paid) / REWARD_PRECISION
+    }
+
+    /// Settles `user`'s accrued rewards so their balance can change safely.
+    fn checkpoint(env: &Env, user: &Address) {
+        Self::accrue(env);
+        let reward_per_share: i128 = env
+            .storage()
+            .instance()
+            .get(&DataKey::RewardPerShare)
+            .unwrap();
+        let earned = Self::earned(env, user, reward_per_share);
+        env.storage()
+            .instance()
+            .set(&DataKey::PendingRewards(user.clone()), &earned);
+        env.storage().instance().set(
+            &DataKey::RewardPerSharePaid(user.clone()),
+            &reward_per_share,
+        );
+    }
+}
+
+#[cfg(test)]
+mod test;
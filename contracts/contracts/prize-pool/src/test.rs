@@ -0,0 +1,151 @@
+#![cfg(test)]
+
+extern crate std;
+
+use soroban_sdk::{
+    testutils::{Address as _, Ledger},
+    token::{StellarAssetClient, TokenClient},
+    Address, Env,
+};
+
+use crate::{PrizePoolContract, PrizePoolContractClient};
+
+fn setup(env: &Env) -> (PrizePoolContractClient<'_>, Address, Address) {
+    let admin = Address::generate(env);
+    let token = env
+        .register_stellar_asset_contract_v2(admin.clone())
+        .address();
+
+    let contract_id = env.register_contract(None, PrizePoolContract);
+    let client = PrizePoolContractClient::new(env, &contract_id);
+
+    env.mock_all_auths();
+    env.ledger().with_mut(|l| l.timestamp = 1_000);
+    client.initialize(&admin, &token);
+
+    let token_admin = StellarAssetClient::new(env, &token);
+    token_admin.mint(&admin, &1_000_000);
+
+    (client, admin, token)
+}
+
+fn set_time(env: &Env, timestamp: u64) {
+    env.ledger().with_mut(|l| l.timestamp = timestamp);
+}
+
+#[test]
+fn test_stream_accrues_linearly() {
+    let env = Env::default();
+    let (client, _admin, token) = setup(&env);
+    let user = Address::generate(&env);
+    StellarAssetClient::new(&env, &token).mint(&user, &1_000);
+
+    client.deposit(&user, &1_000);
+    client.add_prize_stream(&10_000, &100);
+    assert_eq!(client.get_stream_end(), 1_100);
+    assert_eq!(client.get_pending_rewards(&user), 0);
+
+    set_time(&env, 1_025);
+    assert_eq!(client.get_pending_rewards(&user), 2_500);
+
+    set_time(&env, 1_050);
+    assert_eq!(client.get_pending_rewards(&user), 5_000);
+
+    // Nothing more accrues once the stream has ended.
+    set_time(&env, 2_000);
+    assert_eq!(client.get_pending_rewards(&user), 10_000);
+}
+
+#[test]
+fn test_late_depositor_only_earns_from_entry() {
+    let env = Env::default();
+    let (client, _admin, token) = setup(&env);
+    let early = Address::generate(&env);
+    let late = Address::generate(&env);
+    let token_admin = StellarAssetClient::new(&env, &token);
+    token_admin.mint(&early, &1_000);
+    token_admin.mint(&late, &1_000);
+
+    client.deposit(&early, &1_000);
+    client.add_prize_stream(&10_000, &100);
+
+    set_time(&env, 1_050);
+    client.deposit(&late, &1_000);
+
+    set_time(&env, 1_100);
+    assert_eq!(client.get_pending_rewards(&early), 7_500);
+    assert_eq!(client.get_pending_rewards(&late), 2_500);
+}
+
+#[test]
+fn test_withdraw_stops_accrual_and_claim_pays_out() {
+    let env = Env::default();
+    let (client, _admin, token) = setup(&env);
+    let user = Address::generate(&env);
+    let other = Address::generate(&env);
+    let token_admin = StellarAssetClient::new(&env, &token);
+    token_admin.mint(&user, &1_000);
+    token_admin.mint(&other, &1_000);
+
+    client.deposit(&user, &1_000);
+    client.deposit(&other, &1_000);
+    client.add_prize_stream(&10_000, &100);
+
+    set_time(&env, 1_040);
+    client.withdraw(&user, &1_000);
+    assert_eq!(client.get_pending_rewards(&user), 2_000);
+
+    set_time(&env, 1_100);
+    assert_eq!(client.get_pending_rewards(&user), 2_000);
+    assert_eq!(client.get_pending_rewards(&other), 8_000);
+
+    let claimed = client.claim_rewards(&user);
+    assert_eq!(claimed, 2_000);
+    assert_eq!(client.get_pending_rewards(&user), 0);
+    assert_eq!(TokenClient::new(&env, &token).balance(&user), 3_000);
+}
+
+#[test]
+fn test_new_stream_rolls_over_unreleased_amount() {
+    let env = Env::default();
+    let (client, _admin, token) = setup(&env);
+    let user = Address::generate(&env);
+    StellarAssetClient::new(&env, &token).mint(&user, &1_000);
+
+    client.deposit(&user, &1_000);
+    client.add_prize_stream(&10_000, &100);
+
+    // Half released; the remaining 5_000 joins the new 5_000 over 100s.
+    set_time(&env, 1_050);
+    client.add_prize_stream(&5_000, &100);
+    assert_eq!(client.get_stream_end(), 1_150);
+
+    set_time(&env, 1_150);
+    assert_eq!(client.get_pending_rewards(&user), 15_000);
+}
+
+#[test]
+fn test_stream_without_depositors_goes_to_prize_fund() {
+    let env = Env::default();
+    let (client, _admin, token) = setup(&env);
+    let user = Address::generate(&env);
+    StellarAssetClient::new(&env, &token).mint(&user, &1_000);
+
+    client.add_prize_stream(&10_000, &100);
+
+    set_time(&env, 1_030);
+    client.deposit(&user, &1_000);
+    assert_eq!(client.get_prize_fund(), 3_000);
+
+    set_time(&env, 1_100);
+    assert_eq!(client.get_pending_rewards(&user), 7_000);
+}
+
+#[test]
+#[should_panic(expected = "stream duration must be greater than zero")]
+fn test_stream_zero_duration_rejected() {
+    let env = Env::default();
+    let (client, _admin, _token) = setup(&env);
+
+    client.add_prize_stream(&10_000, &0);
+}